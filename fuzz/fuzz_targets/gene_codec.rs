@@ -0,0 +1,39 @@
+//! Builds genes from arbitrary bytes and checks that they decode to what was encoded.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../src/dnaparser.rs"]
+mod dnaparser;
+
+use dnaparser::{
+    DecodedGeneInfo, GeneBias, GeneMirroring, GeneProperty, GeneTag, NeuronType, OutputTag,
+    PropertyValue,
+};
+
+fuzz_target!(|bytes: &[u8]| {
+    let mut values = bytes
+        .iter()
+        .filter_map(|byte| PropertyValue::new(byte % 64));
+    let mut next = || values.next().unwrap_or_default();
+    let flags = bytes.first().copied().unwrap_or_default();
+    let gene = DecodedGeneInfo {
+        neuron_type: NeuronType(next()),
+        tag: GeneTag(next()),
+        properties: [(); 8].map(|()| GeneProperty(next())),
+        bias: GeneBias(next()),
+        ampersand: (flags & 0x40 != 0).then(|| GeneProperty(next())),
+        mirroring: (flags & 0x80 != 0).then(|| GeneMirroring(next())),
+        output_tags: std::iter::from_fn(|| {
+            let tag = values.next()?;
+            let weight = values.next()?;
+            Some(OutputTag { tag, weight })
+        })
+        .collect(),
+    };
+    assert_eq!(
+        gene.encode()
+            .and_then(|text| DecodedGeneInfo::decode(&text)),
+        Ok(gene)
+    );
+});
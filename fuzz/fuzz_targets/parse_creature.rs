@@ -0,0 +1,30 @@
+//! Feeds arbitrary text to the creature and gene parsers. Whatever parses must write back into
+//! text that parses to the same model.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../src/dnaparser.rs"]
+mod dnaparser;
+
+use dnaparser::{CreatureDNA, DecodedGeneInfo};
+
+fuzz_target!(|text: &str| {
+    if let Ok(gene) = DecodedGeneInfo::decode(text) {
+        assert_eq!(
+            gene.encode()
+                .and_then(|text| DecodedGeneInfo::decode(&text)),
+            Ok(gene)
+        );
+    }
+    if let Ok(dna) = CreatureDNA::parse(text) {
+        let written = dna
+            .to_text()
+            .and_then(|text| CreatureDNA::parse(&text))
+            .expect("written creature parses");
+        assert_eq!(
+            written.to_text().and_then(|text| CreatureDNA::parse(&text)),
+            Ok(written)
+        );
+    }
+});
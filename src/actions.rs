@@ -0,0 +1,301 @@
+//! Central list of the editor actions, used by the command palette and the keyboard shortcuts.
+use egui::{Key, KeyboardShortcut, Modifiers};
+
+use std::path::Path;
+
+use crate::dnaparser::CreatureDNA;
+use crate::graph::Clustering;
+use crate::preferences::Keybindings;
+use crate::transform::Axis;
+use crate::workspace::Workspace;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    New,
+    Open,
+    Save,
+    SaveAs,
+    Merge,
+    Import,
+    Export,
+    ExportImage,
+    /// Output tag network as DOT or Mermaid.
+    ExportGraph(Clustering),
+    Close,
+    Undo,
+    Redo,
+    Find,
+    Preferences,
+    ToggleLibrary,
+    ToggleTemplates,
+    ToggleStats,
+    CommandPalette,
+    Validate,
+    /// Flips the grid that is shown, the cells or a DNA layer.
+    Flip(Axis),
+    /// Point mutation of the selected cell or gene.
+    MutateSelection,
+    /// Index into the recent files.
+    OpenRecent(usize),
+    /// Index into the favorite files.
+    OpenFavorite(usize),
+    /// Index into the saved workspaces.
+    OpenWorkspace(usize),
+    JumpToCell(usize),
+    SwitchLayer {
+        dna_idx: usize,
+        layer_idx: usize,
+    },
+}
+
+pub struct ActionEntry {
+    pub action: Action,
+    /// Key of the action in the keybindings. `None` for the actions on parts of the creature,
+    /// which have no shortcut.
+    pub id: Option<&'static str>,
+    pub title: String,
+    pub shortcut: Option<KeyboardShortcut>,
+}
+
+impl ActionEntry {
+    fn new(action: Action, title: impl Into<String>) -> Self {
+        Self {
+            action,
+            id: None,
+            title: title.into(),
+            shortcut: None,
+        }
+    }
+
+    fn global(id: &'static str, action: Action, title: &str) -> Self {
+        Self {
+            id: Some(id),
+            ..Self::new(action, title)
+        }
+    }
+
+    fn with_shortcut(mut self, modifiers: Modifiers, key: Key) -> Self {
+        self.shortcut = Some(KeyboardShortcut::new(modifiers, key));
+        self
+    }
+}
+
+/// Actions that are always available, with the shortcuts of `keybindings`.
+pub fn global_actions(keybindings: &Keybindings) -> Vec<ActionEntry> {
+    let command = Modifiers::COMMAND;
+    let command_shift = Modifiers::COMMAND | Modifiers::SHIFT;
+    let mut entries = vec![
+        ActionEntry::global("new", Action::New, "File: New").with_shortcut(command, Key::N),
+        ActionEntry::global("open", Action::Open, "File: Open").with_shortcut(command, Key::O),
+        ActionEntry::global("save", Action::Save, "File: Save").with_shortcut(command, Key::S),
+        ActionEntry::global("save_as", Action::SaveAs, "File: Save As...")
+            .with_shortcut(command_shift, Key::S),
+        ActionEntry::global("merge", Action::Merge, "File: Merge..."),
+        ActionEntry::global("import", Action::Import, "File: Import..."),
+        ActionEntry::global("export", Action::Export, "File: Export..."),
+        ActionEntry::global("export_image", Action::ExportImage, "File: Export Image..."),
+        ActionEntry::global(
+            "export_graph_category",
+            Action::ExportGraph(Clustering::Category),
+            "File: Export Graph by Category...",
+        ),
+        ActionEntry::global(
+            "export_graph_layer",
+            Action::ExportGraph(Clustering::Layer),
+            "File: Export Graph by Layer...",
+        ),
+        ActionEntry::global("preferences", Action::Preferences, "File: Preferences...")
+            .with_shortcut(command, Key::Comma),
+        ActionEntry::global("close", Action::Close, "File: Close").with_shortcut(command, Key::W),
+        ActionEntry::global("undo", Action::Undo, "Edit: Undo").with_shortcut(command, Key::Z),
+        ActionEntry::global("redo", Action::Redo, "Edit: Redo").with_shortcut(command, Key::Y),
+        ActionEntry::global("redo_alt", Action::Redo, "Edit: Redo (alternate)")
+            .with_shortcut(command_shift, Key::Z),
+        ActionEntry::global("find", Action::Find, "Edit: Find and Replace...")
+            .with_shortcut(command, Key::F),
+        ActionEntry::global(
+            "toggle_library",
+            Action::ToggleLibrary,
+            "View: Toggle Library",
+        ),
+        ActionEntry::global(
+            "toggle_templates",
+            Action::ToggleTemplates,
+            "View: Toggle Templates",
+        ),
+        ActionEntry::global("toggle_stats", Action::ToggleStats, "View: Toggle Stats"),
+        ActionEntry::global(
+            "command_palette",
+            Action::CommandPalette,
+            "View: Command Palette",
+        )
+        .with_shortcut(command_shift, Key::P),
+        ActionEntry::global("validate", Action::Validate, "Creature: Validate"),
+        ActionEntry::global(
+            "flip_horizontal",
+            Action::Flip(Axis::Horizontal),
+            "Transform: Flip Grid Horizontally",
+        ),
+        ActionEntry::global(
+            "flip_vertical",
+            Action::Flip(Axis::Vertical),
+            "Transform: Flip Grid Vertically",
+        ),
+        ActionEntry::global("mutate", Action::MutateSelection, "Mutate: Selected Cell"),
+    ];
+    for entry in entries.iter_mut() {
+        if let Some(id) = entry.id {
+            entry.shortcut = keybindings.shortcut(id, entry.shortcut);
+        }
+    }
+    entries
+}
+
+/// The configured shortcut of `action`, for menus.
+pub fn shortcut_text(ctx: &egui::Context, keybindings: &Keybindings, action: Action) -> String {
+    global_actions(keybindings)
+        .into_iter()
+        .find(|entry| entry.action == action)
+        .and_then(|entry| entry.shortcut)
+        .map(|shortcut| ctx.format_shortcut(&shortcut))
+        .unwrap_or_default()
+}
+
+/// All actions: the global ones plus jumping to each cell and switching to each DNA layer of
+/// `dna`.
+pub fn registry(dna: Option<&CreatureDNA>, keybindings: &Keybindings) -> Vec<ActionEntry> {
+    let mut entries = global_actions(keybindings);
+    let Some(dna) = dna else {
+        return entries;
+    };
+    for (idx, cell) in dna.cells.iter().enumerate() {
+        entries.push(ActionEntry::new(
+            Action::JumpToCell(idx),
+            format!(
+                "Jump to cell [{}][{}] {}",
+                cell.index.x,
+                cell.index.y,
+                cell.decoded.neuron_type.to_name()
+            ),
+        ));
+    }
+    for (dna_idx, block) in dna.dna.iter().enumerate() {
+        for (layer_idx, layer) in block.genes.iter().enumerate() {
+            entries.push(ActionEntry::new(
+                Action::SwitchLayer { dna_idx, layer_idx },
+                format!("Switch to layer dna {dna_idx} z={}", layer.z_level),
+            ));
+        }
+    }
+    entries
+}
+
+/// Opening each favorite, recent file and saved workspace.
+pub fn session_actions(
+    favorites: &[impl AsRef<Path>],
+    recent: &[impl AsRef<Path>],
+    workspaces: &[Workspace],
+) -> Vec<ActionEntry> {
+    let favorites = favorites.iter().enumerate().map(|(idx, path)| {
+        let title = format!("Open Favorite: {}", path.as_ref().display());
+        ActionEntry::new(Action::OpenFavorite(idx), title)
+    });
+    let recent = recent.iter().enumerate().map(|(idx, path)| {
+        let title = format!("Open Recent: {}", path.as_ref().display());
+        ActionEntry::new(Action::OpenRecent(idx), title)
+    });
+    let workspaces = workspaces.iter().enumerate().map(|(idx, workspace)| {
+        let title = format!("Open Workspace: {}", workspace.name);
+        ActionEntry::new(Action::OpenWorkspace(idx), title)
+    });
+    favorites.chain(recent).chain(workspaces).collect()
+}
+
+/// The action of the first shortcut that was pressed, consuming it. Shortcuts with more
+/// modifiers are checked first, `consume_shortcut` ignores extra modifiers.
+pub fn pressed_shortcut(input: &mut egui::InputState, entries: &[ActionEntry]) -> Option<Action> {
+    let mut shortcuts: Vec<(KeyboardShortcut, Action)> = entries
+        .iter()
+        .filter_map(|entry| Some((entry.shortcut?, entry.action)))
+        .collect();
+    shortcuts.sort_by_key(|(shortcut, _)| std::cmp::Reverse(modifier_count(shortcut.modifiers)));
+    shortcuts
+        .into_iter()
+        .find(|(shortcut, _)| input.consume_shortcut(shortcut))
+        .map(|(_, action)| action)
+}
+
+fn modifier_count(modifiers: Modifiers) -> u32 {
+    u32::from(modifiers.alt)
+        + u32::from(modifiers.command || modifiers.ctrl)
+        + u32::from(modifiers.shift)
+}
+
+/// Score of `query` as a fuzzy match of `text`: all query characters must appear in order,
+/// ignoring case. Consecutive characters and characters at the start of words score higher,
+/// skipped characters lower. `None` if `text` does not match.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut previous: Option<usize> = None;
+    for query_char in query.chars().flat_map(char::to_lowercase) {
+        if query_char.is_whitespace() {
+            continue;
+        }
+        let found = pos + text[pos..].iter().position(|c| *c == query_char)?;
+        let word_start = found == 0 || !text[found - 1].is_alphanumeric();
+        score += match previous {
+            Some(previous) if previous + 1 == found => 5,
+            _ if word_start => 3,
+            _ => 1,
+        };
+        score -= (found - pos).min(10) as i32 / 2;
+        previous = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_matches_in_order_and_prefers_word_starts() {
+        assert!(fuzzy_score("sv", "File: Save").is_some());
+        assert!(fuzzy_score("vs", "File: Save").is_none());
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        let word_starts = fuzzy_score("fs", "File: Save").unwrap();
+        let inside = fuzzy_score("fs", "Transform: Flip Grid").unwrap_or(i32::MIN);
+        assert!(word_starts > inside);
+        assert!(fuzzy_score("SAVE", "File: Save As...") > fuzzy_score("save", "Validate ..."));
+    }
+
+    #[test]
+    fn registry_lists_cells_and_layers() {
+        let dna = crate::read_creature("data/e5.txt").expect("failed to read data/e5.txt");
+        let keybindings = Keybindings::default();
+        let entries = registry(Some(&dna), &keybindings);
+        let jumps = entries
+            .iter()
+            .filter(|entry| matches!(entry.action, Action::JumpToCell(_)))
+            .count();
+        assert_eq!(jumps, dna.cells.len());
+        assert!(entries.iter().any(|entry| entry.action
+            == Action::SwitchLayer {
+                dna_idx: 0,
+                layer_idx: 0
+            }));
+        assert_eq!(
+            registry(None, &keybindings).len(),
+            global_actions(&keybindings).len()
+        );
+
+        let mut ids: Vec<_> = entries.iter().filter_map(|entry| entry.id).collect();
+        let count = ids.len();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), count, "action ids are unique");
+    }
+}
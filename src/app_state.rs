@@ -1,9 +1,121 @@
 use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+use crate::preferences::Preferences;
+use crate::workspace::Workspace;
+
+/// Layout of state.json written by this version. Older files are migrated when read.
+pub const STATE_VERSION: u32 = 2;
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct AppState {
+    /// Layout version, files without one are version 1.
+    #[serde(default)]
+    pub version: u32,
+    /// File of the active tab.
     pub open_file: Option<PathBuf>,
     pub last_folder: Option<PathBuf>,
+    /// Files of all open tabs, in tab order.
+    #[serde(default)]
+    pub open_files: Vec<PathBuf>,
+    /// Folder shown in the creature library panel.
+    #[serde(default)]
+    pub library_folder: Option<PathBuf>,
+    #[serde(default)]
+    pub show_library: bool,
+    #[serde(default)]
+    pub show_templates: bool,
+    #[serde(default)]
+    pub show_stats: bool,
+    #[serde(default)]
+    pub preferences: Preferences,
+    /// Most recently opened or saved first.
+    #[serde(default)]
+    pub recent_files: Vec<PathBuf>,
+    /// Files pinned at the top of the recent files.
+    #[serde(default)]
+    pub favorite_files: Vec<PathBuf>,
+    #[serde(default)]
+    pub workspaces: Vec<Workspace>,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self {
+            version: STATE_VERSION,
+            open_file: None,
+            last_folder: None,
+            open_files: Vec::new(),
+            library_folder: None,
+            show_library: false,
+            show_templates: false,
+            show_stats: false,
+            preferences: Preferences::default(),
+            recent_files: Vec::new(),
+            favorite_files: Vec::new(),
+            workspaces: Vec::new(),
+        }
+    }
+}
+
+impl AppState {
+    /// Reads state.json, migrating the layouts of older versions.
+    pub fn from_json(bytes: &[u8]) -> Result<Self, String> {
+        let mut value: Value =
+            serde_json::from_slice(bytes).map_err(|err| format!("invalid state: {err}"))?;
+        let version = value.get("version").and_then(Value::as_u64).unwrap_or(1);
+        if version > u64::from(STATE_VERSION) {
+            return Err(format!(
+                "state version {version} is newer than this editor ({STATE_VERSION})"
+            ));
+        }
+        if version < 2 {
+            migrate_v1(&mut value);
+        }
+
+        let mut state: AppState =
+            serde_json::from_value(value).map_err(|err| format!("invalid state: {err}"))?;
+        state.version = STATE_VERSION;
+        Ok(state)
+    }
+}
+
+/// Version 1 kept the neuron palette next to the open files.
+fn migrate_v1(value: &mut Value) {
+    let Some(state) = value.as_object_mut() else {
+        return;
+    };
+    let Some(palette) = state.remove("neuron_palette") else {
+        return;
+    };
+    let preferences = state
+        .entry("preferences")
+        .or_insert_with(|| Value::Object(Map::new()));
+    if let Some(preferences) = preferences.as_object_mut() {
+        preferences.insert("neuron_palette".to_string(), palette);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_version_1() {
+        let v1 = br#"{"open_file":null,"last_folder":"/tmp","neuron_palette":"eeeeeeeeee"}"#;
+        let state = AppState::from_json(v1).expect("version 1 is readable");
+        assert_eq!(state.version, STATE_VERSION);
+        assert_eq!(state.last_folder, Some(PathBuf::from("/tmp")));
+        assert_eq!(String::from(state.preferences.neuron_palette), "eeeeeeeeee");
+        assert_eq!(state.preferences.creator_name, "creator");
+
+        let written = serde_json::to_vec(&state).expect("state serializes");
+        let read = AppState::from_json(&written).expect("written state is readable");
+        assert_eq!(read.preferences, state.preferences);
+
+        let newer = br#"{"version":99,"open_file":null,"last_folder":null}"#;
+        assert!(AppState::from_json(newer).is_err());
+    }
 }
@@ -0,0 +1,270 @@
+//! Command line interface. Running the editor without arguments starts the GUI.
+use std::path::{Path, PathBuf};
+
+use crate::exchange::{self, Format};
+use crate::graph::{self, Clustering, GraphFormat};
+use crate::merge::{self, Side};
+use crate::render::{self, RenderOptions};
+use crate::stats::{CostTable, CreatureStats};
+use crate::{read_creature, save_creature};
+
+const USAGE: &str = "\
+usage:
+  gridworld-editor                                    start the editor
+  gridworld-editor merge <base> <ours> <theirs> [-o <output>] [--favor base|ours|theirs]
+      three-way merge of creature files. Writes to <ours> unless -o is given, so it can be
+      used as a git merge driver:  driver = gridworld-editor merge %O %A %B
+      exits with 1 if there were conflicts (resolved using --favor, default ours)
+  gridworld-editor convert <input> <output> [--from <format>] [--to <format>]
+      converts between the game format (txt) and json, ron or yaml. Formats default to the
+      file extensions; use - as output to print to stdout
+  gridworld-editor render <input> <output> [--cell-size <pixels>] [--labels] [--arrows]
+      draws the cells to a png or svg image, optionally with neuron labels and output tag
+      arrows
+  gridworld-editor graph <input> <output> [--format dot|mermaid] [--cluster category|layer]
+      writes the output tag network as Graphviz DOT or Mermaid, with nodes grouped by
+      neuron category (default) or DNA layer. The format defaults to the file extension
+      (.dot, .gv, .mmd); use - as output to print to stdout
+  gridworld-editor info <input> [--costs <costs.json>]
+      prints statistics: cells per kind and category, genes per layer, connections and
+      weights, fan-in/fan-out, bounding box, symmetry and, given a json object of neuron
+      kind names and costs like {\"eye\": 0.2}, the energy upkeep
+  gridworld-editor help                               show this message";
+
+/// Runs the command given by `args` (without the program name) and returns the exit code.
+pub fn run(args: &[String]) -> i32 {
+    let result = match args.first().map(String::as_str) {
+        Some("merge") => merge_command(&args[1..]),
+        Some("convert") => convert_command(&args[1..]),
+        Some("render") => render_command(&args[1..]),
+        Some("graph") => graph_command(&args[1..]),
+        Some("info") => info_command(&args[1..]),
+        Some("help" | "-h" | "--help") => {
+            println!("{USAGE}");
+            Ok(0)
+        }
+        Some(other) => Err(format!("unknown command '{other}'\n{USAGE}")),
+        None => Err(USAGE.to_string()),
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("{err}");
+            2
+        }
+    }
+}
+
+fn merge_command(args: &[String]) -> Result<i32, String> {
+    let mut paths = Vec::new();
+    let mut output = None;
+    let mut favor = Side::Ours;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => {
+                let path = args.next().ok_or("missing path after -o")?;
+                output = Some(PathBuf::from(path));
+            }
+            "--favor" => {
+                let side = args.next().ok_or("missing side after --favor")?;
+                favor = Side::ALL
+                    .into_iter()
+                    .find(|s| s.name() == side)
+                    .ok_or_else(|| format!("invalid side '{side}'"))?;
+            }
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+
+    let [base_path, ours_path, theirs_path] = paths.as_slice() else {
+        return Err(format!("merge expects three files\n{USAGE}"));
+    };
+    let base = read_creature(base_path)?;
+    let ours = read_creature(ours_path)?;
+    let theirs = read_creature(theirs_path)?;
+
+    let outcome = merge::merge_with(&base, &ours, &theirs, |_, _| favor);
+    for conflict in &outcome.conflicts {
+        eprintln!("conflict: {}", conflict.title());
+        for side in Side::ALL {
+            eprintln!("  {:<6} {}", side.name(), conflict.side_text(side));
+        }
+    }
+
+    let output = output.as_ref().unwrap_or(ours_path);
+    save_creature(output, &outcome.merged)?;
+
+    if outcome.conflicts.is_empty() {
+        Ok(0)
+    } else {
+        eprintln!(
+            "{} conflict(s), resolved as {}",
+            outcome.conflicts.len(),
+            favor.name()
+        );
+        Ok(1)
+    }
+}
+
+fn convert_command(args: &[String]) -> Result<i32, String> {
+    let mut paths = Vec::new();
+    let mut from = None;
+    let mut to = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" | "--to" => {
+                let name = args
+                    .next()
+                    .ok_or_else(|| format!("missing format after {arg}"))?;
+                let format =
+                    Format::from_name(name).ok_or_else(|| format!("unknown format '{name}'"))?;
+                if arg == "--from" {
+                    from = Some(format);
+                } else {
+                    to = Some(format);
+                }
+            }
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+
+    let [input, output] = paths.as_slice() else {
+        return Err(format!(
+            "convert expects an input and an output file\n{USAGE}"
+        ));
+    };
+    let from = from
+        .or_else(|| Format::from_path(input))
+        .ok_or_else(|| format!("cannot tell the format of '{}'", input.display()))?;
+    let to_stdout = output == Path::new("-");
+    let to = to
+        .or_else(|| Format::from_path(output))
+        .or(to_stdout.then_some(Format::Json))
+        .ok_or_else(|| format!("cannot tell the format of '{}'", output.display()))?;
+
+    let content = std::fs::read_to_string(input)
+        .map_err(|err| format!("failed to read '{}': {err}", input.display()))?;
+    let dna = exchange::import(&content, from)
+        .map_err(|err| format!("failed to parse '{}': {err}", input.display()))?;
+    let converted = exchange::export(&dna, to)?;
+
+    if to_stdout {
+        print!("{converted}");
+    } else {
+        std::fs::write(output, converted)
+            .map_err(|err| format!("failed to write '{}': {err}", output.display()))?;
+    }
+    Ok(0)
+}
+
+fn render_command(args: &[String]) -> Result<i32, String> {
+    let mut paths = Vec::new();
+    let mut options = RenderOptions::default();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--cell-size" => {
+                let size = args.next().ok_or("missing size after --cell-size")?;
+                options.cell_size = size
+                    .parse()
+                    .ok()
+                    .filter(|size: &f32| (1.0..=512.0).contains(size))
+                    .ok_or_else(|| format!("invalid cell size '{size}'"))?;
+            }
+            "--labels" => options.labels = true,
+            "--arrows" => options.arrows = true,
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+
+    let [input, output] = paths.as_slice() else {
+        return Err(format!(
+            "render expects an input and an output file\n{USAGE}"
+        ));
+    };
+    let dna = read_creature(input)?;
+    render::write_image(output, &dna, &options)?;
+    Ok(0)
+}
+
+fn graph_command(args: &[String]) -> Result<i32, String> {
+    let mut paths = Vec::new();
+    let mut format = None;
+    let mut clustering = Clustering::default();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                let name = args.next().ok_or("missing format after --format")?;
+                format = Some(
+                    GraphFormat::from_name(name)
+                        .ok_or_else(|| format!("unknown graph format '{name}'"))?,
+                );
+            }
+            "--cluster" => {
+                let name = args.next().ok_or("missing clustering after --cluster")?;
+                clustering = Clustering::from_name(name)
+                    .ok_or_else(|| format!("unknown clustering '{name}'"))?;
+            }
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+
+    let [input, output] = paths.as_slice() else {
+        return Err(format!(
+            "graph expects an input and an output file\n{USAGE}"
+        ));
+    };
+    let to_stdout = output.as_os_str() == "-";
+    let format = match format {
+        Some(format) => format,
+        None if to_stdout => GraphFormat::Dot,
+        None => GraphFormat::from_path(output).ok_or_else(|| {
+            format!(
+                "cannot tell the graph format of '{}', use --format",
+                output.display()
+            )
+        })?,
+    };
+    let dna = read_creature(input)?;
+    let content = graph::export(&dna, format, clustering);
+    if to_stdout {
+        print!("{content}");
+    } else {
+        std::fs::write(output, content)
+            .map_err(|err| format!("failed to write '{}': {err}", output.display()))?;
+    }
+    Ok(0)
+}
+
+fn info_command(args: &[String]) -> Result<i32, String> {
+    let mut paths = Vec::new();
+    let mut costs = CostTable::default();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--costs" => {
+                let path = Path::new(args.next().ok_or("missing file after --costs")?);
+                let json = std::fs::read_to_string(path)
+                    .map_err(|err| format!("failed to read '{}': {err}", path.display()))?;
+                costs = CostTable::from_json(&json)?;
+            }
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+
+    let [input] = paths.as_slice() else {
+        return Err(format!("info expects one input file\n{USAGE}"));
+    };
+    let dna = read_creature(input)?;
+    print!("{}", CreatureStats::new(&dna, &costs).report());
+    Ok(0)
+}
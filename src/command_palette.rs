@@ -0,0 +1,115 @@
+use egui::{Key, Modifiers, RichText, ScrollArea};
+
+use crate::actions::{Action, ActionEntry, fuzzy_score};
+
+const MAX_RESULTS: usize = 50;
+
+/// Window searching all registered actions, opened with Ctrl+Shift+P.
+#[derive(Default)]
+pub struct CommandPalette {
+    open: bool,
+    query: String,
+    /// Highlighted row among the matches.
+    selected: usize,
+}
+
+impl CommandPalette {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    /// Returns the action the user picked.
+    pub fn ui(&mut self, ctx: &egui::Context, entries: &[ActionEntry]) -> Option<Action> {
+        if !self.open {
+            return None;
+        }
+
+        let mut matches: Vec<(i32, &ActionEntry)> = entries
+            .iter()
+            .filter_map(|entry| Some((fuzzy_score(&self.query, &entry.title)?, entry)))
+            .collect();
+        // Stable, so equal scores keep the registry order.
+        matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        matches.truncate(MAX_RESULTS);
+
+        let (up, down, enter, escape) = ctx.input_mut(|i| {
+            (
+                i.consume_key(Modifiers::NONE, Key::ArrowUp),
+                i.consume_key(Modifiers::NONE, Key::ArrowDown),
+                i.consume_key(Modifiers::NONE, Key::Enter),
+                i.consume_key(Modifiers::NONE, Key::Escape),
+            )
+        });
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if down {
+            self.selected += 1;
+        }
+        self.selected = self.selected.min(matches.len().saturating_sub(1));
+
+        let mut picked = enter
+            .then(|| matches.get(self.selected).map(|(_, entry)| entry.action))
+            .flatten();
+        egui::Window::new("Command Palette")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 48.0])
+            .fixed_size([420.0, 0.0])
+            .show(ctx, |ui| {
+                let search = ui.add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text("Type a command")
+                        .desired_width(f32::INFINITY),
+                );
+                search.request_focus();
+                if search.changed() {
+                    self.selected = 0;
+                }
+                ui.separator();
+
+                ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+                    if matches.is_empty() {
+                        ui.label(RichText::new("No matching command").weak());
+                    }
+                    for (row, (_, entry)) in matches.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            let response = ui.selectable_label(row == self.selected, &entry.title);
+                            if row == self.selected && (up || down) {
+                                response.scroll_to_me(None);
+                            }
+                            if response.clicked() {
+                                picked = Some(entry.action);
+                            }
+                            if let Some(shortcut) = entry.shortcut {
+                                ui.with_layout(
+                                    egui::Layout::right_to_left(egui::Align::Center),
+                                    |ui| {
+                                        ui.label(
+                                            RichText::new(ctx.format_shortcut(&shortcut)).weak(),
+                                        );
+                                    },
+                                );
+                            }
+                        });
+                    }
+                });
+            });
+
+        if picked.is_some() || escape {
+            self.open = false;
+        }
+        picked
+    }
+}
@@ -0,0 +1,183 @@
+//! Structural diff of two creatures, matching records the same way as `merge`.
+use std::fmt;
+
+use crate::dnaparser::{CreatureDNA, DecodedGeneInfo, DnaData, GridIndex2};
+use crate::merge::{
+    DnaField, GeneField, MergeField, RecordLocation, TextField, find_gene, ordered_keys,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Text {
+        field: TextField,
+        old: Option<String>,
+        new: Option<String>,
+    },
+    Comments,
+    DnaBlockAdded(usize),
+    DnaBlockRemoved(usize),
+    DnaField {
+        dna_idx: usize,
+        field: DnaField,
+    },
+    RecordAdded(RecordLocation),
+    RecordRemoved(RecordLocation),
+    RecordChanged {
+        location: RecordLocation,
+        fields: Vec<GeneField>,
+    },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Text { field, old, new } => write!(
+                f,
+                "{}: {} -> {}",
+                field.name(),
+                old.as_deref().unwrap_or("-"),
+                new.as_deref().unwrap_or("-")
+            ),
+            Change::Comments => write!(f, "comments changed"),
+            Change::DnaBlockAdded(dna_idx) => write!(f, "dna {dna_idx} added"),
+            Change::DnaBlockRemoved(dna_idx) => write!(f, "dna {dna_idx} removed"),
+            Change::DnaField { dna_idx, field } => {
+                write!(f, "dna {dna_idx} {} changed", field.name())
+            }
+            Change::RecordAdded(location) => write!(f, "{location} added"),
+            Change::RecordRemoved(location) => write!(f, "{location} removed"),
+            Change::RecordChanged { location, fields } => {
+                let names: Vec<String> = fields.iter().map(|field| field.name()).collect();
+                write!(f, "{location} changed: {}", names.join(", "))
+            }
+        }
+    }
+}
+
+/// Lists what changed from `old` to `new`.
+pub fn diff(old: &CreatureDNA, new: &CreatureDNA) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    for field in TextField::ALL {
+        if field.get(old) != field.get(new) {
+            changes.push(Change::Text {
+                field,
+                old: field.get(old).clone(),
+                new: field.get(new).clone(),
+            });
+        }
+    }
+
+    let cell_keys = ordered_keys(
+        old.cells.iter().map(|cell| cell.index),
+        new.cells.iter().map(|cell| cell.index),
+    );
+    for index in cell_keys {
+        diff_record(
+            &mut changes,
+            RecordLocation::Cell(index),
+            old.cells.get_cell_at(index.x, index.y),
+            new.cells.get_cell_at(index.x, index.y),
+        );
+    }
+
+    for dna_idx in 0..old.dna.len().max(new.dna.len()) {
+        match (old.dna.get(dna_idx), new.dna.get(dna_idx)) {
+            (Some(old_block), Some(new_block)) => {
+                diff_block(&mut changes, dna_idx, old_block, new_block)
+            }
+            (None, Some(_)) => changes.push(Change::DnaBlockAdded(dna_idx)),
+            (Some(_), None) => changes.push(Change::DnaBlockRemoved(dna_idx)),
+            (None, None) => {}
+        }
+    }
+
+    if old.comments != new.comments {
+        changes.push(Change::Comments);
+    }
+
+    changes
+}
+
+fn diff_block(changes: &mut Vec<Change>, dna_idx: usize, old: &DnaData, new: &DnaData) {
+    for field in DnaField::ALL {
+        if !field.same(old, new) {
+            changes.push(Change::DnaField { dna_idx, field });
+        }
+    }
+
+    let gene_keys = |block: &DnaData| -> Vec<(u16, GridIndex2)> {
+        block
+            .genes
+            .iter()
+            .flat_map(|layer| layer.genes.iter().map(|gene| (layer.z_level, gene.index)))
+            .collect()
+    };
+    for (z_level, index) in ordered_keys(gene_keys(old), gene_keys(new)) {
+        let location = RecordLocation::Gene {
+            dna_idx,
+            z_level,
+            index,
+        };
+        diff_record(
+            changes,
+            location,
+            find_gene(old, z_level, index),
+            find_gene(new, z_level, index),
+        );
+    }
+}
+
+fn diff_record(
+    changes: &mut Vec<Change>,
+    location: RecordLocation,
+    old: Option<&DecodedGeneInfo>,
+    new: Option<&DecodedGeneInfo>,
+) {
+    match (old, new) {
+        (Some(old), Some(new)) => {
+            let fields: Vec<GeneField> = GeneField::ALL
+                .into_iter()
+                .filter(|field| !field.same(old, new))
+                .collect();
+            if !fields.is_empty() {
+                changes.push(Change::RecordChanged { location, fields });
+            }
+        }
+        (None, Some(_)) => changes.push(Change::RecordAdded(location)),
+        (Some(_), None) => changes.push(Change::RecordRemoved(location)),
+        (None, None) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dnaparser::parse_creature_dna;
+
+    #[test]
+    fn diff_lists_structural_changes() {
+        let input = std::fs::read_to_string("data/e5.txt").expect("failed to read data/e5.txt");
+        let old = parse_creature_dna(&input).expect("failed to parse e5");
+        assert!(diff(&old, &old).is_empty());
+
+        let mut new = old.clone();
+        let removed = new.cells.remove(1).index;
+        new.cells[0].decoded.bias.0.increase();
+        new.cells[0].decoded.tag.0.increase();
+        new.dna.push(DnaData::default());
+
+        let changes = diff(&old, &new);
+        assert_eq!(
+            changes,
+            vec![
+                Change::RecordChanged {
+                    location: RecordLocation::Cell(old.cells[0].index),
+                    fields: vec![GeneField::Tag, GeneField::Bias],
+                },
+                Change::RecordRemoved(RecordLocation::Cell(removed)),
+                Change::DnaBlockAdded(old.dna.len()),
+            ]
+        );
+    }
+}
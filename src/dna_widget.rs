@@ -1,13 +1,63 @@
-use egui::{Button, CollapsingHeader, Grid, Key, RichText, ScrollArea, Ui, WidgetText};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
+use egui::{
+    Button, CollapsingHeader, Grid, Id, Key, Modifiers, RichText, ScrollArea, Ui, WidgetText,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::dnaparser::PropertyValueRepresentation::{self, *};
 use crate::dnaparser::{
-    CreatureDNA, DecodedGeneInfo, DnaCreatorRecord, DnaNameRecord, GeneRecord, GridIndex2,
-    NeuronProperties, PropertyValue,
+    ChannelColor, CreatureDNA, DecodedGeneInfo, DnaCreatorRecord, DnaData, DnaNameRecord,
+    GeneMirroring, GeneProperty, GeneRecord, GridIndex2, NeuronProperties, NeuronType, OutputTag,
+    PropertyValue,
+};
+use crate::merge::RecordLocation;
+use crate::pdf_infos::{
+    color_channel_props, default_properties, lookup_prop_info, property_representation,
 };
-use crate::pdf_infos::lookup_prop_info;
+use crate::preferences::Preferences;
+use crate::search::{self, TagRename};
+use crate::template::StampTarget;
+use crate::transform::{self, Axis};
+use crate::validate;
+use neuron_picker::neuron_type_picker;
 
+mod find;
 mod grid_widget;
+mod neuron_picker;
 
+/// DNA block and layer edits requested in the sidebar, applied after the tree has been drawn.
+enum TreeCommand {
+    AddBlock,
+    DuplicateBlock {
+        dna_idx: usize,
+    },
+    DeleteBlock {
+        dna_idx: usize,
+    },
+    PlaceBlock {
+        dna_idx: usize,
+        location: GridIndex2,
+    },
+    AddLayer {
+        dna_idx: usize,
+    },
+    DuplicateLayer {
+        dna_idx: usize,
+        layer_idx: usize,
+    },
+    DeleteLayer {
+        dna_idx: usize,
+        layer_idx: usize,
+    },
+    SetZLevel {
+        dna_idx: usize,
+        layer_idx: usize,
+        z_level: u16,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
 enum CellGridMode {
     Cells,
     DnaGenesLayer { dna_idx: usize, layer_idx: usize },
@@ -16,8 +66,102 @@ enum CellGridMode {
 #[derive(Default)]
 pub struct DnaWidget {
     selected_path: Option<String>,
+    onion_skin: OnionSkin,
+    /// Grid position moved with the arrow keys. Follows the selected cell or gene.
+    cursor: Option<GridIndex2>,
+    find: find::FindState,
+    /// Positions marked with Ctrl+click, in the grid of `marked_mode`.
+    marked: HashSet<GridIndex2>,
+    marked_mode: Option<CellGridMode>,
+}
+
+/// Neuron kinds the number keys 1 to 9 and 0 assign to the cell or gene at the grid cursor.
+/// Stored as ten letters of the encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct NeuronPalette(pub [NeuronType; 10]);
+
+impl Default for NeuronPalette {
+    fn default() -> Self {
+        let names = [
+            "neuron",
+            "eye",
+            "fin",
+            "jet",
+            "mouth",
+            "photosynthesis",
+            "blank cell",
+            "armor",
+            "storage cell",
+            "dna",
+        ];
+        Self(names.map(|name| NeuronType::from_name(name).expect("known neuron name")))
+    }
+}
+
+impl From<NeuronPalette> for String {
+    fn from(palette: NeuronPalette) -> Self {
+        palette
+            .0
+            .iter()
+            .map(|neuron_type| neuron_type.to_char())
+            .collect()
+    }
+}
+
+impl TryFrom<String> for NeuronPalette {
+    type Error = String;
+
+    fn try_from(letters: String) -> Result<Self, Self::Error> {
+        let types = letters
+            .chars()
+            .map(|c| PropertyValue::from_char(c).map(NeuronType))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| format!("invalid neuron letter in palette '{letters}'"))?;
+        let types: [NeuronType; 10] = types
+            .try_into()
+            .map_err(|_| format!("palette '{letters}' needs 10 letters"))?;
+        Ok(Self(types))
+    }
+}
+
+/// Keys assigning the palette slots, in slot order.
+const PALETTE_KEYS: [Key; 10] = [
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+    Key::Num0,
+];
+
+/// Value fields drawn in the detail editor this frame, in order, so Tab can cycle through them.
+#[derive(Clone, Default)]
+struct ValueFields {
+    ids: Vec<Id>,
+    /// Field Tab was pressed in, and whether to move forward (Tab) or back (Shift+Tab).
+    tab_from: Option<(Id, bool)>,
+}
+
+fn value_fields_id() -> Id {
+    Id::new("dna_widget_value_fields")
 }
 
+/// Faded display of the other layers of a DNA block in the gene grid.
+#[derive(Default)]
+struct OnionSkin {
+    enabled: bool,
+    /// Layers switched off by the user, as (dna_idx, z_level).
+    hidden: HashSet<(usize, u16)>,
+}
+
+/// Genes of other layers at one grid position, as (z_level, neuron name).
+type Ghosts = HashMap<GridIndex2, Vec<(u16, &'static str)>>;
+
 impl DnaWidget {
     pub fn new() -> Self {
         Self::default()
@@ -27,16 +171,220 @@ impl DnaWidget {
         self.selected_path = None;
     }
 
-    pub fn sidebar_ui(&mut self, ui: &mut Ui, dna: Option<&CreatureDNA>) {
+    pub fn selected_path(&self) -> Option<&str> {
+        self.selected_path.as_deref()
+    }
+
+    /// Find and Replace panel. Its matches are highlighted in the grids until `close_find`.
+    pub fn find_ui(&mut self, ui: &mut Ui, dna: &mut CreatureDNA) {
+        find::find_ui(ui, dna, &mut self.find, &mut self.selected_path);
+    }
+
+    pub fn close_find(&mut self) {
+        self.find.matches.clear();
+    }
+
+    /// Records marked with Ctrl+click in the grid that is shown, to save as a template.
+    pub fn marked_records(&self, dna: &CreatureDNA) -> Vec<(GridIndex2, DecodedGeneInfo)> {
+        let records: Vec<(GridIndex2, &DecodedGeneInfo)> = match self.marked_mode {
+            None => Vec::new(),
+            Some(CellGridMode::Cells) => dna
+                .cells
+                .iter()
+                .map(|cell| (cell.index, &cell.decoded))
+                .collect(),
+            Some(CellGridMode::DnaGenesLayer { dna_idx, layer_idx }) => dna
+                .dna
+                .get(dna_idx)
+                .and_then(|block| block.genes.get(layer_idx))
+                .map(|layer| {
+                    layer
+                        .genes
+                        .iter()
+                        .map(|gene| (gene.index, &gene.decoded))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+        records
+            .into_iter()
+            .filter(|(index, _)| self.marked.contains(index))
+            .map(|(index, info)| (index, info.clone()))
+            .collect()
+    }
+
+    /// The grid that is shown and the cursor in it, where templates are stamped.
+    pub fn stamp_target(&self) -> Option<(StampTarget, GridIndex2)> {
+        let target = match cell_grid_mode_from_path(self.selected_path.as_deref()?)? {
+            CellGridMode::Cells => StampTarget::Cells,
+            CellGridMode::DnaGenesLayer { dna_idx, layer_idx } => {
+                StampTarget::Layer { dna_idx, layer_idx }
+            }
+        };
+        Some((target, self.cursor.unwrap_or_default()))
+    }
+
+    /// Selects a tree item, e.g. a cell or layer picked in the command palette.
+    pub fn select(&mut self, path: String) {
+        self.selected_path = Some(path);
+    }
+
+    /// Flips the grid that is shown. Returns `false` if the selection has no grid.
+    pub fn flip_grid(&self, dna: &mut CreatureDNA, axis: Axis) -> bool {
+        match self
+            .selected_path
+            .as_deref()
+            .and_then(cell_grid_mode_from_path)
+        {
+            Some(CellGridMode::Cells) => transform::flip_cells(dna, axis),
+            Some(CellGridMode::DnaGenesLayer { dna_idx, layer_idx }) => {
+                let Some(layer) = dna
+                    .dna
+                    .get_mut(dna_idx)
+                    .and_then(|block| block.genes.get_mut(layer_idx))
+                else {
+                    return false;
+                };
+                transform::flip_layer(layer, axis);
+            }
+            None => return false,
+        }
+        true
+    }
+
+    /// Point mutation of the selected cell or gene. Returns a description of the change.
+    pub fn mutate_selection(&self, dna: &mut CreatureDNA) -> Result<String, String> {
+        let info = self
+            .selected_path
+            .as_deref()
+            .and_then(|path| selected_decoded_info_mut(dna, path))
+            .ok_or_else(|| "Select a cell or gene to mutate.".to_string())?;
+        Ok(transform::mutate(info, transform::random_bits())
+            .unwrap_or_else(|| "The mutated value was already at its limit.".to_string()))
+    }
+
+    pub fn sidebar_ui(
+        &mut self,
+        ui: &mut Ui,
+        dna: Option<&mut CreatureDNA>,
+        preferences: &Preferences,
+    ) {
+        let mut commands = Vec::new();
         ScrollArea::vertical().show(ui, |ui| {
-            section(ui, "CreatureDNA", true, |ui| match dna {
-                Some(dna) => self.loaded_tree_ui(ui, dna),
+            section(ui, "CreatureDNA", true, |ui| match dna.as_deref() {
+                Some(dna) => self.loaded_tree_ui(ui, dna, &mut commands),
                 None => self.empty_tree_ui(ui),
             });
         });
+        if let Some(dna) = dna {
+            for command in commands {
+                self.apply_tree_command(dna, command, preferences);
+            }
+        }
     }
 
-    pub fn detail_ui(&mut self, ui: &mut Ui, mut dna: Option<&mut CreatureDNA>) {
+    fn apply_tree_command(
+        &mut self,
+        dna: &mut CreatureDNA,
+        command: TreeCommand,
+        preferences: &Preferences,
+    ) {
+        match command {
+            TreeCommand::AddBlock => {
+                let taken: Vec<String> = dna
+                    .dna
+                    .iter()
+                    .filter_map(|block| block.dna_name.as_ref().map(|record| record.name.clone()))
+                    .collect();
+                let name = DnaData::generate_name(taken.iter().map(String::as_str));
+                let location = free_dna_cell(dna);
+                dna.dna.push(DnaData::new(
+                    name,
+                    preferences.creator_name.clone(),
+                    location,
+                ));
+                self.selected_path = Some(format!("CreatureDNA/dna/{}", dna.dna.len() - 1));
+            }
+            TreeCommand::DuplicateBlock { dna_idx } => {
+                let Some(mut copy) = dna.dna.get(dna_idx).cloned() else {
+                    return;
+                };
+                let taken: Vec<String> = dna
+                    .dna
+                    .iter()
+                    .filter_map(|block| block.dna_name.as_ref().map(|record| record.name.clone()))
+                    .collect();
+                copy.rename(DnaData::generate_name(taken.iter().map(String::as_str)));
+                // Without a free cell the copy is left unplaced rather than on the original's
+                // cell, so that validation reports it.
+                copy.place_at(free_dna_cell(dna));
+                dna.dna.insert(dna_idx + 1, copy);
+                self.selected_path = Some(format!("CreatureDNA/dna/{}", dna_idx + 1));
+            }
+            TreeCommand::DeleteBlock { dna_idx } => {
+                if dna_idx < dna.dna.len() {
+                    dna.dna.remove(dna_idx);
+                    if self
+                        .selected_path
+                        .as_deref()
+                        .is_some_and(|path| path.starts_with("CreatureDNA/dna/"))
+                    {
+                        self.selected_path = Some("CreatureDNA/dna".to_string());
+                    }
+                }
+            }
+            TreeCommand::PlaceBlock { dna_idx, location } => {
+                if let Some(block) = dna.dna.get_mut(dna_idx) {
+                    block.place_at(Some(location));
+                }
+            }
+            TreeCommand::AddLayer { dna_idx } => {
+                if let Some(block) = dna.dna.get_mut(dna_idx) {
+                    let layer_idx = block.genes.add_layer();
+                    self.selected_path =
+                        Some(format!("CreatureDNA/dna/{dna_idx}/genes/{layer_idx}"));
+                }
+            }
+            TreeCommand::DuplicateLayer { dna_idx, layer_idx } => {
+                if let Some(new_idx) = dna
+                    .dna
+                    .get_mut(dna_idx)
+                    .and_then(|block| block.genes.duplicate_layer(layer_idx))
+                {
+                    self.selected_path = Some(format!("CreatureDNA/dna/{dna_idx}/genes/{new_idx}"));
+                }
+            }
+            TreeCommand::DeleteLayer { dna_idx, layer_idx } => {
+                if let Some(block) = dna.dna.get_mut(dna_idx)
+                    && layer_idx < block.genes.len()
+                {
+                    block.genes.remove(layer_idx);
+                    // Paths into later layers would now point at the wrong layer.
+                    if self.selected_path.as_deref().is_some_and(|path| {
+                        path.starts_with(&format!("CreatureDNA/dna/{dna_idx}/genes/"))
+                    }) {
+                        self.selected_path = Some(format!("CreatureDNA/dna/{dna_idx}"));
+                    }
+                }
+            }
+            TreeCommand::SetZLevel {
+                dna_idx,
+                layer_idx,
+                z_level,
+            } => {
+                if let Some(block) = dna.dna.get_mut(dna_idx) {
+                    block.genes.set_z_level(layer_idx, z_level);
+                }
+            }
+        }
+    }
+
+    pub fn detail_ui(
+        &mut self,
+        ui: &mut Ui,
+        mut dna: Option<&mut CreatureDNA>,
+        preferences: &mut Preferences,
+    ) {
         ui.label(RichText::new("Selected Component").strong());
         ui.separator();
         let Some(path) = self.selected_path.clone() else {
@@ -47,28 +395,114 @@ impl DnaWidget {
         ui.label(&path);
         ui.separator();
 
+        let mut focus_first_field = false;
         if let Some(grid_mode) = cell_grid_mode_from_path(&path) {
             if let Some(dna_ref) = dna.as_deref_mut() {
-                cell_grid_ui(ui, dna_ref, &mut self.selected_path, grid_mode);
+                if self.marked_mode != Some(grid_mode) {
+                    self.marked.clear();
+                    self.marked_mode = Some(grid_mode);
+                }
+                let highlights = self.find_highlights(dna_ref, grid_mode);
+                let mut grid = CellGrid {
+                    dna: dna_ref,
+                    selected_path: &mut self.selected_path,
+                    cursor: &mut self.cursor,
+                    mode: grid_mode,
+                    size: preferences.grid_size,
+                    highlights,
+                    marked: &mut self.marked,
+                };
+                focus_first_field = grid.keys_ui(ui, &preferences.neuron_palette);
+                cell_grid_ui(ui, &mut grid, &mut self.onion_skin);
+                palette_ui(ui, &mut preferences.neuron_palette);
             }
             ui.separator();
         }
 
+        ui.data_mut(|data| data.insert_temp(value_fields_id(), ValueFields::default()));
+
         let detail_path = self.selected_path.clone().unwrap_or(path);
         ui.push_id(("dna_detail", &detail_path), |ui| {
-            if let Some(dna_ref) = dna.as_deref_mut() {
-                if selected_string_entry_ui(ui, dna_ref, &detail_path) {
-                    return;
-                }
+            if let Some(dna_ref) = dna.as_deref_mut()
+                && selected_string_entry_ui(ui, dna_ref, &detail_path)
+            {
+                return;
             }
 
-            match dna.and_then(|dna| selected_decoded_info_mut(dna, &detail_path)) {
-                Some(gene_info) => decoded_info_ui(ui, gene_info),
+            let carriers = dna
+                .as_deref()
+                .map(|dna| tag_carriers(dna, &detail_path))
+                .unwrap_or_default();
+            match dna
+                .as_deref_mut()
+                .and_then(|dna| selected_decoded_info_mut(dna, &detail_path))
+            {
+                Some(gene_info) => {
+                    decoded_info_ui(ui, gene_info, &carriers);
+                    let tag = gene_info.tag.0;
+                    if let Some(dna) = dna {
+                        ui.separator();
+                        rename_tag_ui(ui, dna, tag);
+                    }
+                }
                 None => {
                     ui.label("No decoded details for the current selection.");
                 }
             }
         });
+
+        let fields: ValueFields = ui
+            .data_mut(|data| data.remove_temp(value_fields_id()))
+            .unwrap_or_default();
+        let target = match fields.tab_from {
+            Some((from, forward)) => fields
+                .ids
+                .iter()
+                .position(|id| *id == from)
+                .map(|pos| {
+                    let len = fields.ids.len();
+                    if forward {
+                        (pos + 1) % len
+                    } else {
+                        (pos + len - 1) % len
+                    }
+                })
+                .map(|pos| fields.ids[pos]),
+            None if focus_first_field => fields.ids.first().copied(),
+            None => None,
+        };
+        if let Some(id) = target {
+            ui.memory_mut(|memory| memory.request_focus(id));
+        }
+    }
+
+    /// Grid positions of the find matches in the grid of `mode`.
+    fn find_highlights(&self, dna: &CreatureDNA, mode: CellGridMode) -> HashSet<GridIndex2> {
+        let z_level = match mode {
+            CellGridMode::Cells => None,
+            CellGridMode::DnaGenesLayer { dna_idx, layer_idx } => dna
+                .dna
+                .get(dna_idx)
+                .and_then(|block| block.genes.get(layer_idx))
+                .map(|layer| (dna_idx, layer.z_level)),
+        };
+        self.find
+            .matches
+            .iter()
+            .filter_map(|location| match (*location, mode, z_level) {
+                (RecordLocation::Cell(index), CellGridMode::Cells, _) => Some(index),
+                (
+                    RecordLocation::Gene {
+                        dna_idx,
+                        z_level,
+                        index,
+                    },
+                    _,
+                    Some(layer),
+                ) if layer == (dna_idx, z_level) => Some(index),
+                _ => None,
+            })
+            .collect()
     }
 
     fn empty_tree_ui(&mut self, ui: &mut Ui) {
@@ -77,11 +511,11 @@ impl DnaWidget {
         }
     }
 
-    fn loaded_tree_ui(&mut self, ui: &mut Ui, dna: &CreatureDNA) {
+    fn loaded_tree_ui(&mut self, ui: &mut Ui, dna: &CreatureDNA, commands: &mut Vec<TreeCommand>) {
         self.metadata_ui(ui);
         self.creature_ui(ui);
         self.cells_ui(ui, dna);
-        self.dna_ui(ui, dna);
+        self.dna_ui(ui, dna, commands);
         self.comments_ui(ui, dna);
     }
 
@@ -113,8 +547,21 @@ impl DnaWidget {
         });
     }
 
-    fn dna_ui(&mut self, ui: &mut Ui, dna: &CreatureDNA) {
+    fn dna_ui(&mut self, ui: &mut Ui, dna: &CreatureDNA, commands: &mut Vec<TreeCommand>) {
+        let issues = validate::validate(dna);
         section(ui, format!("dna ({})", dna.dna.len()), true, |ui| {
+            for issue in issues.iter().filter(|issue| issue.dna_idx().is_none()) {
+                ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {issue}"));
+            }
+            if ui.small_button("Add DNA block").clicked() {
+                commands.push(TreeCommand::AddBlock);
+            }
+            let dna_cells: Vec<GridIndex2> = dna
+                .cells
+                .iter()
+                .filter(|cell| cell.decoded.neuron_type.is_dna())
+                .map(|cell| cell.index)
+                .collect();
             for (dna_idx, dna_entry) in dna.dna.iter().enumerate() {
                 let name = dna_entry
                     .dna_name
@@ -133,6 +580,46 @@ impl DnaWidget {
                     self.leaf(ui, "dna_name", &format!("{block_path}/dna_name"));
                     self.leaf(ui, "dna_location", &format!("{block_path}/dna_location"));
                     self.leaf(ui, "dna_creator", &format!("{block_path}/dna_creator"));
+                    ui.horizontal(|ui| {
+                        let location_text = dna_entry
+                            .dna_location
+                            .map(|index| format!("[{}][{}]", index.x, index.y))
+                            .unwrap_or_else(|| "none".to_string());
+                        egui::ComboBox::from_id_salt(("dna_block_location", dna_idx))
+                            .selected_text(format!("at {location_text}"))
+                            .show_ui(ui, |ui| {
+                                if dna_cells.is_empty() {
+                                    ui.label("No dna cells in this creature.");
+                                }
+                                for &location in &dna_cells {
+                                    let selected = dna_entry.dna_location == Some(location);
+                                    let label = format!("[{}][{}]", location.x, location.y);
+                                    if ui.selectable_label(selected, label).clicked() {
+                                        commands
+                                            .push(TreeCommand::PlaceBlock { dna_idx, location });
+                                    }
+                                }
+                            })
+                            .response
+                            .on_hover_text(
+                                "Move dna_location, dna_name and dna_creator to a dna cell",
+                            );
+                        if ui.small_button("Duplicate").clicked() {
+                            commands.push(TreeCommand::DuplicateBlock { dna_idx });
+                        }
+                        if ui.small_button("Delete").clicked() {
+                            commands.push(TreeCommand::DeleteBlock { dna_idx });
+                        }
+                    });
+                    for issue in issues
+                        .iter()
+                        .filter(|issue| issue.dna_idx() == Some(dna_idx))
+                    {
+                        ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {issue}"));
+                    }
+                    if ui.small_button("Add layer").clicked() {
+                        commands.push(TreeCommand::AddLayer { dna_idx });
+                    }
 
                     for (layer_idx, layer) in dna_entry.genes.iter().enumerate() {
                         section_with_id(
@@ -141,6 +628,39 @@ impl DnaWidget {
                             false,
                             ("dna_layer", dna_idx, layer_idx),
                             |ui| {
+                                ui.horizontal(|ui| {
+                                    let mut z_level = layer.z_level;
+                                    ui.label("z-level");
+                                    if ui.add(egui::DragValue::new(&mut z_level)).changed() {
+                                        commands.push(TreeCommand::SetZLevel {
+                                            dna_idx,
+                                            layer_idx,
+                                            z_level,
+                                        });
+                                    }
+                                    if ui
+                                        .small_button("Edit")
+                                        .on_hover_text("Show the layer grid")
+                                        .clicked()
+                                    {
+                                        self.selected_path = Some(format!(
+                                            "CreatureDNA/dna/{dna_idx}/genes/{layer_idx}"
+                                        ));
+                                    }
+                                    if ui.small_button("Duplicate").clicked() {
+                                        commands.push(TreeCommand::DuplicateLayer {
+                                            dna_idx,
+                                            layer_idx,
+                                        });
+                                    }
+                                    if ui.small_button("Delete").clicked() {
+                                        commands
+                                            .push(TreeCommand::DeleteLayer { dna_idx, layer_idx });
+                                    }
+                                });
+                                if layer.genes.is_empty() {
+                                    ui.label(RichText::new("empty layers are not saved").weak());
+                                }
                                 for (gene_idx, gene) in layer.genes.iter().enumerate() {
                                     let gene_label = format!(
                                         "[{}][{}][{}] {}",
@@ -187,12 +707,14 @@ impl DnaWidget {
     }
 }
 
-fn cell_grid_ui(
-    ui: &mut Ui,
-    dna: &mut CreatureDNA,
-    selected_path: &mut Option<String>,
-    mode: CellGridMode,
-) {
+fn cell_grid_ui(ui: &mut Ui, grid: &mut CellGrid, onion_skin: &mut OnionSkin) {
+    let mode = grid.mode;
+    let dna = &mut *grid.dna;
+    let selected_path = &mut *grid.selected_path;
+    let cursor = &mut *grid.cursor;
+    let highlights = &grid.highlights;
+    let marked = &mut *grid.marked;
+    let last = grid.size.saturating_sub(1);
     let (max_x, max_y) = match mode {
         CellGridMode::Cells => {
             let max_x = dna
@@ -200,15 +722,15 @@ fn cell_grid_ui(
                 .iter()
                 .map(|cell| cell.index.x)
                 .max()
-                .unwrap_or(8)
-                .max(8);
+                .unwrap_or(last)
+                .max(last);
             let max_y = dna
                 .cells
                 .iter()
                 .map(|cell| cell.index.y)
                 .max()
-                .unwrap_or(8)
-                .max(8);
+                .unwrap_or(last)
+                .max(last);
             (max_x, max_y)
         }
         CellGridMode::DnaGenesLayer { dna_idx, layer_idx } => {
@@ -225,31 +747,32 @@ fn cell_grid_ui(
                 .iter()
                 .map(|gene| gene.index.x)
                 .max()
-                .unwrap_or(8)
-                .max(8);
+                .unwrap_or(last)
+                .max(last);
             let max_y = layer
                 .genes
                 .iter()
                 .map(|gene| gene.index.y)
                 .max()
-                .unwrap_or(8)
-                .max(8);
+                .unwrap_or(last)
+                .max(last);
             (max_x, max_y)
         }
     };
 
-    let delete_pressed = ui.input(|i| i.key_pressed(Key::Delete) || i.key_pressed(Key::Backspace));
+    // Backspace in the value fields below must not delete the cell being edited.
+    let delete_pressed = !ui.ctx().wants_keyboard_input()
+        && ui.input(|i| i.key_pressed(Key::Delete) || i.key_pressed(Key::Backspace));
     if delete_pressed {
         match &mode {
             CellGridMode::Cells => {
                 if let Some(selected_idx) = selected_path
                     .as_deref()
                     .and_then(selected_cell_idx_from_path)
+                    && selected_idx < dna.cells.len()
                 {
-                    if selected_idx < dna.cells.len() {
-                        dna.cells.remove(selected_idx);
-                        *selected_path = Some("CreatureDNA/cells".to_owned());
-                    }
+                    dna.cells.remove(selected_idx);
+                    *selected_path = Some("CreatureDNA/cells".to_owned());
                 }
             }
             CellGridMode::DnaGenesLayer { dna_idx, layer_idx } => {
@@ -257,31 +780,39 @@ fn cell_grid_ui(
                     selected_path
                         .as_deref()
                         .and_then(selected_gene_idx_from_path)
+                    && selected_dna_idx == *dna_idx
+                    && selected_layer_idx == *layer_idx
+                    && let Some(layer) = dna
+                        .dna
+                        .get_mut(*dna_idx)
+                        .and_then(|dna_block| dna_block.genes.get_mut(*layer_idx))
+                    && selected_gene_idx < layer.genes.len()
                 {
-                    if selected_dna_idx == *dna_idx && selected_layer_idx == *layer_idx {
-                        if let Some(layer) = dna
-                            .dna
-                            .get_mut(*dna_idx)
-                            .and_then(|dna_block| dna_block.genes.get_mut(*layer_idx))
-                        {
-                            if selected_gene_idx < layer.genes.len() {
-                                layer.genes.remove(selected_gene_idx);
-                                if layer.genes.is_empty() {
-                                    *selected_path = Some(format!("CreatureDNA/dna/{dna_idx}"));
-                                } else {
-                                    let next_idx = selected_gene_idx.min(layer.genes.len() - 1);
-                                    *selected_path = Some(format!(
-                                        "CreatureDNA/dna/{dna_idx}/genes/{layer_idx}/{next_idx}"
-                                    ));
-                                }
-                            }
-                        }
+                    layer.genes.remove(selected_gene_idx);
+                    if layer.genes.is_empty() {
+                        *selected_path = Some(format!("CreatureDNA/dna/{dna_idx}"));
+                    } else {
+                        let next_idx = selected_gene_idx.min(layer.genes.len() - 1);
+                        *selected_path = Some(format!(
+                            "CreatureDNA/dna/{dna_idx}/genes/{layer_idx}/{next_idx}"
+                        ));
                     }
                 }
             }
         }
     }
 
+    let ghosts = match mode {
+        CellGridMode::Cells => Ghosts::new(),
+        CellGridMode::DnaGenesLayer { dna_idx, layer_idx } => {
+            onion_skin_ui(ui, dna, onion_skin, dna_idx, layer_idx)
+        }
+    };
+    let max_x = ghosts.keys().map(|index| index.x).fold(max_x, u16::max);
+    let max_y = ghosts.keys().map(|index| index.y).fold(max_y, u16::max);
+    let max_x = cursor.map_or(max_x, |cursor| cursor.x.max(max_x));
+    let max_y = cursor.map_or(max_y, |cursor| cursor.y.max(max_y));
+
     let columns = usize::from(max_x) + 1;
     let spacing_x = ui.spacing().item_spacing.x;
     let total_spacing = spacing_x * (columns.saturating_sub(1) as f32);
@@ -296,7 +827,7 @@ fn cell_grid_ui(
         .show(ui, |ui| {
             for y in 0..=max_y {
                 for x in 0..=max_x {
-                    match mode {
+                    let response = match mode {
                         CellGridMode::Cells => {
                             if let Some((idx, cell)) = dna
                                 .cells
@@ -306,16 +837,16 @@ fn cell_grid_ui(
                             {
                                 let cell_path = format!("CreatureDNA/cells/{idx}");
                                 let selected = selected_path.as_deref() == Some(cell_path.as_str());
-                                if grid_cell_response(
+                                let response = grid_cell_response(
                                     ui,
                                     selected,
                                     cell.decoded.neuron_type.to_name(),
                                     min_col_width,
-                                )
-                                .clicked()
-                                {
+                                );
+                                if response.clicked() {
                                     *selected_path = Some(cell_path);
                                 }
+                                response
                             } else {
                                 let response = grid_cell_response(ui, false, "_", min_col_width);
                                 if response.double_clicked() {
@@ -326,6 +857,7 @@ fn cell_grid_ui(
                                     let new_idx = dna.cells.len() - 1;
                                     *selected_path = Some(format!("CreatureDNA/cells/{new_idx}"));
                                 }
+                                response
                             }
                         }
                         CellGridMode::DnaGenesLayer { dna_idx, layer_idx } => {
@@ -343,36 +875,93 @@ fn cell_grid_ui(
                                             (gene_idx, gene.decoded.neuron_type.to_name())
                                         })
                                 });
+                            let ghosts_here = ghosts.get(&GridIndex2 { x, y });
                             if let Some((gene_idx, gene_name)) = gene_at_pos {
                                 let gene_path = format!(
                                     "CreatureDNA/dna/{dna_idx}/genes/{layer_idx}/{gene_idx}"
                                 );
                                 let selected = selected_path.as_deref() == Some(gene_path.as_str());
-                                if grid_cell_response(ui, selected, gene_name, min_col_width)
-                                    .clicked()
-                                {
+                                let mut button = Button::new(gene_name).selected(selected);
+                                if ghosts_here.is_some() {
+                                    button = button
+                                        .stroke(egui::Stroke::new(2.0, ui.visuals().warn_fg_color));
+                                }
+                                let mut response = ui.add_sized(
+                                    [min_col_width, ui.spacing().interact_size.y],
+                                    button,
+                                );
+                                if let Some(ghosts_here) = ghosts_here {
+                                    response = response.on_hover_text(format!(
+                                        "Overlaps {}",
+                                        ghost_list(ghosts_here)
+                                    ));
+                                }
+                                if response.clicked() {
                                     *selected_path = Some(gene_path);
                                 }
+                                response
                             } else {
-                                let response = grid_cell_response(ui, false, "_", min_col_width);
-                                if response.double_clicked() {
-                                    if let Some(layer) = dna
+                                let response = match ghosts_here {
+                                    Some(ghosts_here) => ui
+                                        .add_sized(
+                                            [min_col_width, ui.spacing().interact_size.y],
+                                            Button::new(
+                                                RichText::new(ghosts_here[0].1).weak().italics(),
+                                            )
+                                            .frame(false),
+                                        )
+                                        .on_hover_text(ghost_list(ghosts_here)),
+                                    None => grid_cell_response(ui, false, "_", min_col_width),
+                                };
+                                if response.double_clicked()
+                                    && let Some(layer) = dna
                                         .dna
                                         .get_mut(dna_idx)
                                         .and_then(|dna_block| dna_block.genes.get_mut(layer_idx))
-                                    {
-                                        layer.genes.push(GeneRecord {
-                                            index: GridIndex2 { x, y },
-                                            decoded: std::default::Default::default(),
-                                        });
-                                        let new_idx = layer.genes.len() - 1;
-                                        *selected_path = Some(format!(
-                                            "CreatureDNA/dna/{dna_idx}/genes/{layer_idx}/{new_idx}"
-                                        ));
-                                    }
+                                {
+                                    layer.genes.push(GeneRecord {
+                                        index: GridIndex2 { x, y },
+                                        decoded: std::default::Default::default(),
+                                    });
+                                    let new_idx = layer.genes.len() - 1;
+                                    *selected_path = Some(format!(
+                                        "CreatureDNA/dna/{dna_idx}/genes/{layer_idx}/{new_idx}"
+                                    ));
                                 }
+                                response
                             }
                         }
+                    };
+
+                    let index = GridIndex2 { x, y };
+                    if response.clicked() {
+                        *cursor = Some(index);
+                        if ui.input(|i| i.modifiers.command) && !marked.remove(&index) {
+                            marked.insert(index);
+                        }
+                    }
+                    if marked.contains(&index) {
+                        ui.painter().rect_filled(
+                            response.rect,
+                            2.0,
+                            ui.visuals().selection.bg_fill.gamma_multiply(0.5),
+                        );
+                    }
+                    if highlights.contains(&index) {
+                        ui.painter().rect_stroke(
+                            response.rect.shrink(1.0),
+                            2.0,
+                            egui::Stroke::new(2.0, ui.visuals().hyperlink_color),
+                            egui::StrokeKind::Inside,
+                        );
+                    }
+                    if *cursor == Some(index) {
+                        ui.painter().rect_stroke(
+                            response.rect.expand(1.0),
+                            2.0,
+                            ui.visuals().selection.stroke,
+                            egui::StrokeKind::Outside,
+                        );
                     }
                 }
                 ui.end_row();
@@ -380,6 +969,275 @@ fn cell_grid_ui(
         });
 }
 
+/// A cell grid or the gene grid of one DNA layer, with the selection and cursor it edits.
+struct CellGrid<'a> {
+    dna: &'a mut CreatureDNA,
+    selected_path: &'a mut Option<String>,
+    cursor: &'a mut Option<GridIndex2>,
+    mode: CellGridMode,
+    /// Rows and columns shown at least.
+    size: u16,
+    /// Positions of find matches.
+    highlights: HashSet<GridIndex2>,
+    /// Positions marked with Ctrl+click.
+    marked: &'a mut HashSet<GridIndex2>,
+}
+
+impl CellGrid<'_> {
+    /// Keyboard editing: arrows move the cursor and select what is under it, Insert or N
+    /// create a record at the cursor and the number keys give it the kind of a palette slot.
+    /// Returns whether Enter asked to focus the detail editor.
+    fn keys_ui(&mut self, ui: &Ui, palette: &NeuronPalette) -> bool {
+        if let Some(idx) = self.selected_record() {
+            *self.cursor = self.record_indices().get(idx).copied();
+        }
+        if ui.ctx().wants_keyboard_input() {
+            return false;
+        }
+
+        let (step, insert, slot, enter) = ui.input(|i| {
+            let step = [
+                (Key::ArrowLeft, -1, 0),
+                (Key::ArrowRight, 1, 0),
+                (Key::ArrowUp, 0, -1),
+                (Key::ArrowDown, 0, 1),
+            ]
+            .into_iter()
+            .find(|(key, _, _)| i.key_pressed(*key))
+            .map(|(_, dx, dy)| (dx, dy));
+            let plain = i.modifiers.is_none();
+            let insert = i.key_pressed(Key::Insert) || (plain && i.key_pressed(Key::N));
+            let slot = PALETTE_KEYS
+                .iter()
+                .position(|key| plain && i.key_pressed(*key));
+            (step, insert, slot, i.key_pressed(Key::Enter))
+        });
+
+        let cursor = self.cursor.unwrap_or_default();
+        if let Some((dx, dy)) = step {
+            let last = self.size.saturating_sub(1);
+            let indices = self.record_indices();
+            let limit_x = indices
+                .iter()
+                .map(|index| index.x)
+                .fold(last, u16::max)
+                .saturating_add(1);
+            let limit_y = indices
+                .iter()
+                .map(|index| index.y)
+                .fold(last, u16::max)
+                .saturating_add(1);
+            let index = GridIndex2 {
+                x: cursor.x.saturating_add_signed(dx).min(limit_x),
+                y: cursor.y.saturating_add_signed(dy).min(limit_y),
+            };
+            *self.cursor = Some(index);
+            *self.selected_path = Some(match self.record_at(index) {
+                Some(idx) => self.record_path(idx),
+                None => self.grid_path(),
+            });
+        }
+        if insert || slot.is_some() {
+            *self.cursor = Some(cursor);
+            let idx = self
+                .record_at(cursor)
+                .or_else(|| self.insert_record(cursor));
+            if let Some(idx) = idx {
+                if let Some(slot) = slot
+                    && let Some(info) = self.record_info_mut(idx)
+                {
+                    info.neuron_type = palette.0[slot];
+                }
+                *self.selected_path = Some(self.record_path(idx));
+            }
+        }
+        enter
+    }
+
+    /// Positions of the cells or genes, by record index.
+    fn record_indices(&self) -> Vec<GridIndex2> {
+        match self.mode {
+            CellGridMode::Cells => self.dna.cells.iter().map(|cell| cell.index).collect(),
+            CellGridMode::DnaGenesLayer { dna_idx, layer_idx } => self
+                .dna
+                .dna
+                .get(dna_idx)
+                .and_then(|block| block.genes.get(layer_idx))
+                .map(|layer| layer.genes.iter().map(|gene| gene.index).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn record_at(&self, index: GridIndex2) -> Option<usize> {
+        self.record_indices()
+            .iter()
+            .position(|record_index| *record_index == index)
+    }
+
+    fn selected_record(&self) -> Option<usize> {
+        let path = self.selected_path.as_deref()?;
+        match self.mode {
+            CellGridMode::Cells => selected_cell_idx_from_path(path),
+            CellGridMode::DnaGenesLayer { dna_idx, layer_idx } => selected_gene_idx_from_path(path)
+                .filter(|(d, l, _)| (*d, *l) == (dna_idx, layer_idx))
+                .map(|(_, _, gene_idx)| gene_idx),
+        }
+    }
+
+    fn record_path(&self, idx: usize) -> String {
+        format!("{}/{idx}", self.grid_path())
+    }
+
+    fn grid_path(&self) -> String {
+        match self.mode {
+            CellGridMode::Cells => "CreatureDNA/cells".to_owned(),
+            CellGridMode::DnaGenesLayer { dna_idx, layer_idx } => {
+                format!("CreatureDNA/dna/{dna_idx}/genes/{layer_idx}")
+            }
+        }
+    }
+
+    /// Adds a default record at `index` and returns its record index.
+    fn insert_record(&mut self, index: GridIndex2) -> Option<usize> {
+        match self.mode {
+            CellGridMode::Cells => {
+                self.dna.cells.push(NeuronProperties {
+                    index,
+                    decoded: Default::default(),
+                });
+                Some(self.dna.cells.len() - 1)
+            }
+            CellGridMode::DnaGenesLayer { dna_idx, layer_idx } => {
+                let layer = self.dna.dna.get_mut(dna_idx)?.genes.get_mut(layer_idx)?;
+                layer.genes.push(GeneRecord {
+                    index,
+                    decoded: Default::default(),
+                });
+                Some(layer.genes.len() - 1)
+            }
+        }
+    }
+
+    fn record_info_mut(&mut self, idx: usize) -> Option<&mut DecodedGeneInfo> {
+        match self.mode {
+            CellGridMode::Cells => self.dna.cells.get_mut(idx).map(|cell| &mut cell.decoded),
+            CellGridMode::DnaGenesLayer { dna_idx, layer_idx } => self
+                .dna
+                .dna
+                .get_mut(dna_idx)?
+                .genes
+                .get_mut(layer_idx)?
+                .genes
+                .get_mut(idx)
+                .map(|gene| &mut gene.decoded),
+        }
+    }
+}
+
+/// Editor for the kinds the number keys assign.
+fn palette_ui(ui: &mut Ui, palette: &mut NeuronPalette) {
+    section(ui, "Key palette", false, |ui| {
+        ui.label(
+            RichText::new(
+                "Arrows move the cursor, Enter edits, Insert or N adds, Tab cycles the fields.",
+            )
+            .weak(),
+        );
+        ui.horizontal_wrapped(|ui| {
+            for (slot, neuron_type) in palette.0.iter_mut().enumerate() {
+                ui.push_id(slot, |ui| {
+                    ui.label(format!("{}:", (slot + 1) % 10));
+                    if let Some(choice) = neuron_type_picker(ui, *neuron_type) {
+                        *neuron_type = choice.neuron_type;
+                    }
+                });
+            }
+        });
+    });
+}
+
+/// The first "dna" cell no block is placed at yet.
+fn free_dna_cell(dna: &CreatureDNA) -> Option<GridIndex2> {
+    dna.cells
+        .iter()
+        .filter(|cell| cell.decoded.neuron_type.is_dna())
+        .map(|cell| cell.index)
+        .find(|index| {
+            !dna.dna
+                .iter()
+                .any(|block| block.dna_location == Some(*index))
+        })
+}
+
+/// Toggles for the onion skin above a gene grid. Returns the genes of the visible other layers.
+fn onion_skin_ui(
+    ui: &mut Ui,
+    dna: &CreatureDNA,
+    onion_skin: &mut OnionSkin,
+    dna_idx: usize,
+    layer_idx: usize,
+) -> Ghosts {
+    let mut ghosts = Ghosts::new();
+    let Some(block) = dna.dna.get(dna_idx) else {
+        return ghosts;
+    };
+    let others: Vec<_> = block
+        .genes
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| *idx != layer_idx)
+        .map(|(_, layer)| layer)
+        .collect();
+    if others.is_empty() {
+        return ghosts;
+    }
+
+    ui.horizontal_wrapped(|ui| {
+        ui.checkbox(&mut onion_skin.enabled, "Onion skin");
+        if !onion_skin.enabled {
+            return;
+        }
+        for layer in &others {
+            let key = (dna_idx, layer.z_level);
+            let mut visible = !onion_skin.hidden.contains(&key);
+            if ui
+                .checkbox(&mut visible, format!("z={}", layer.z_level))
+                .changed()
+            {
+                if visible {
+                    onion_skin.hidden.remove(&key);
+                } else {
+                    onion_skin.hidden.insert(key);
+                }
+            }
+        }
+    });
+    if !onion_skin.enabled {
+        return ghosts;
+    }
+
+    for layer in others {
+        if onion_skin.hidden.contains(&(dna_idx, layer.z_level)) {
+            continue;
+        }
+        for gene in &layer.genes {
+            ghosts
+                .entry(gene.index)
+                .or_default()
+                .push((layer.z_level, gene.decoded.neuron_type.to_name()));
+        }
+    }
+    ghosts
+}
+
+fn ghost_list(ghosts: &[(u16, &str)]) -> String {
+    let entries: Vec<String> = ghosts
+        .iter()
+        .map(|(z_level, name)| format!("z={z_level}: {name}"))
+        .collect();
+    entries.join(", ")
+}
+
 fn selected_cell_idx_from_path(path: &str) -> Option<usize> {
     path.strip_prefix("CreatureDNA/cells/")?
         .parse::<usize>()
@@ -405,7 +1263,8 @@ fn cell_grid_mode_from_path(path: &str) -> Option<CellGridMode> {
 
     let parts: Vec<&str> = path.split('/').collect();
     match parts.as_slice() {
-        ["CreatureDNA", "dna", dna_idx, "genes", layer_idx, _gene_idx] => {
+        ["CreatureDNA", "dna", dna_idx, "genes", layer_idx]
+        | ["CreatureDNA", "dna", dna_idx, "genes", layer_idx, _] => {
             Some(CellGridMode::DnaGenesLayer {
                 dna_idx: dna_idx.parse::<usize>().ok()?,
                 layer_idx: layer_idx.parse::<usize>().ok()?,
@@ -415,7 +1274,7 @@ fn cell_grid_mode_from_path(path: &str) -> Option<CellGridMode> {
     }
 }
 
-fn decoded_info_ui(ui: &mut Ui, gene_info: &mut DecodedGeneInfo) {
+fn decoded_info_ui(ui: &mut Ui, gene_info: &mut DecodedGeneInfo, carriers: &TagCarriers) {
     let neuron_char = gene_info.neuron_type.0.to_char();
 
     ui.label(RichText::new("Decoded Info").strong());
@@ -424,30 +1283,45 @@ fn decoded_info_ui(ui: &mut Ui, gene_info: &mut DecodedGeneInfo) {
         .num_columns(3)
         .show(ui, |ui| {
             ui.label("Neuron Type");
-            let neuron_text = gene_info.neuron_type.to_name().to_string();
-            property_value_ui(
-                ui,
-                "decoded_neuron_type",
-                &mut gene_info.neuron_type.0,
-                neuron_text,
-            );
+            ui.horizontal(|ui| {
+                property_value_ui(
+                    ui,
+                    "decoded_neuron_type",
+                    &mut gene_info.neuron_type.0,
+                    PChar,
+                );
+                if let Some(choice) = neuron_type_picker(ui, gene_info.neuron_type) {
+                    gene_info.neuron_type = choice.neuron_type;
+                    if choice.reset_properties
+                        && let Some(neuron_char) = choice.neuron_type.0.to_char()
+                    {
+                        for (prop, default) in gene_info
+                            .properties
+                            .iter_mut()
+                            .zip(default_properties(neuron_char))
+                        {
+                            prop.0 = PropertyValue::new(default).unwrap_or_default();
+                        }
+                    }
+                }
+            });
             ui.label("-");
             ui.end_row();
 
             ui.label("Tag");
-            let tag_text = property_char(gene_info.tag.0);
-            property_value_ui(ui, "decoded_tag", &mut gene_info.tag.0, tag_text);
+            property_value_ui(ui, "decoded_tag", &mut gene_info.tag.0, PChar);
             ui.label("-");
             ui.end_row();
 
             for (idx, prop) in gene_info.properties.iter_mut().enumerate() {
                 ui.label(format!("Property {}", idx));
-                let prop_text = property_char(prop.0);
+                let representation =
+                    neuron_char.map_or(PInt, |ch| property_representation(ch, idx as u8));
                 property_value_ui(
                     ui,
-                    format!("decoded_property_{idx}"),
+                    format!("decoded_property_{idx}_{representation:?}"),
                     &mut prop.0,
-                    prop_text,
+                    representation,
                 );
                 let description = neuron_char
                     .and_then(|ch| lookup_prop_info(ch, (idx) as u8))
@@ -457,63 +1331,283 @@ fn decoded_info_ui(ui: &mut Ui, gene_info: &mut DecodedGeneInfo) {
             }
 
             ui.label("Bias");
-            let bias_text = format!("{:.3}", gene_info.bias.0.as_bias());
-            property_value_ui(ui, "decoded_bias", &mut gene_info.bias.0, bias_text);
+            property_value_ui(ui, "decoded_bias", &mut gene_info.bias.0, PBias);
             ui.label("-");
             ui.end_row();
 
             ui.label("Ampersand");
-            if let Some(ampersand) = gene_info.ampersand.as_mut() {
-                let ampersand_text = property_char(ampersand.0);
-                property_value_ui(ui, "decoded_ampersand", &mut ampersand.0, ampersand_text);
-            } else {
-                ui.label("-");
-            }
+            ui.horizontal(|ui| {
+                let mut present = gene_info.ampersand.is_some();
+                if ui
+                    .checkbox(&mut present, "")
+                    .on_hover_text("Whether the gene has an & field")
+                    .changed()
+                {
+                    gene_info.ampersand = present.then(GeneProperty::default);
+                }
+                if let Some(ampersand) = gene_info.ampersand.as_mut() {
+                    property_value_ui(ui, "decoded_ampersand", &mut ampersand.0, PInt);
+                }
+            });
             ui.label("-");
             ui.end_row();
 
             ui.label("Mirroring");
-            let mirror_text = gene_info.mirroring.0.as_mirror().to_string();
-            property_value_ui(
-                ui,
-                "decoded_mirroring",
-                &mut gene_info.mirroring.0,
-                mirror_text,
-            );
+            mirroring_combo(ui, &mut gene_info.mirroring);
             ui.label("-");
             ui.end_row();
         });
 
+    if let Some(channels) = neuron_char.and_then(color_channel_props) {
+        ui.separator();
+        ui.label(RichText::new("Color").strong());
+        let props = channels.map(usize::from);
+        let mut color = ChannelColor(props.map(|prop| gene_info.properties[prop].0));
+        if channel_color_ui(ui, "decoded_color", &mut color) {
+            for (prop, channel) in props.into_iter().zip(color.0) {
+                gene_info.properties[prop].0 = channel;
+            }
+        }
+    }
+
     ui.separator();
-    ui.label(RichText::new("Output Tags").strong());
-    if gene_info.output_tags.is_empty() {
+    output_tags_ui(ui, &mut gene_info.output_tags, carriers);
+}
+
+/// Renames the tag of the selected record in every record carrying it and in all output tags
+/// addressing it, after showing which records change.
+fn rename_tag_ui(ui: &mut Ui, dna: &mut CreatureDNA, from: PropertyValue) {
+    section(ui, "Rename tag", false, |ui| {
+        let id = ui.id().with("rename_tag_to");
+        let mut to = ui
+            .data(|data| data.get_temp::<PropertyValue>(id))
+            .unwrap_or(from);
+        ui.horizontal(|ui| {
+            ui.label(format!("{} →", property_char(from)));
+            property_value_ui(ui, "rename_tag_to", &mut to, PChar);
+        });
+        ui.data_mut(|data| data.insert_temp(id, to));
+
+        let rename = TagRename::preview(dna, from, to);
+        let location_list = |ui: &mut Ui, title: &str, locations: &[RecordLocation]| {
+            ui.label(format!("{title} ({})", locations.len()));
+            for location in locations {
+                ui.label(RichText::new(format!("  {location}")).weak());
+            }
+        };
+        ScrollArea::vertical()
+            .id_salt("rename_tag_preview")
+            .max_height(160.0)
+            .show(ui, |ui| {
+                location_list(ui, "Carrying the tag", &rename.carriers);
+                location_list(ui, "Addressing it in output tags", &rename.references);
+            });
+        if !rename.collisions.is_empty() {
+            ui.colored_label(
+                ui.visuals().error_fg_color,
+                format!(
+                    "⚠ Tag {} is already carried by {}",
+                    property_char(to),
+                    rename
+                        .collisions
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            );
+        }
+
+        if !rename.dangling.is_empty() {
+            ui.colored_label(
+                ui.visuals().error_fg_color,
+                format!(
+                    "⚠ Output tags of {} already address tag {}",
+                    rename
+                        .dangling
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    property_char(to)
+                ),
+            );
+        }
+
+        let enabled = to != from && !rename.merges_connections();
+        if ui.add_enabled(enabled, Button::new("Rename")).clicked()
+            && search::rename_tag(dna, from, to).is_ok()
+        {
+            ui.data_mut(|data| data.remove::<PropertyValue>(id));
+        }
+    });
+}
+
+/// Cells carrying each tag, as labels like `[3][4] eye`, keyed by the raw tag value.
+type TagCarriers = BTreeMap<u8, Vec<String>>;
+
+/// Possible output tag targets of the selected record: all cells for a cell, and the genes of
+/// the same layer for a gene.
+fn tag_carriers(dna: &CreatureDNA, path: &str) -> TagCarriers {
+    let mut carriers = TagCarriers::new();
+    let mut add = |index: GridIndex2, decoded: &DecodedGeneInfo| {
+        carriers
+            .entry(decoded.tag.0.raw())
+            .or_default()
+            .push(format!(
+                "[{}][{}] {}",
+                index.x,
+                index.y,
+                decoded.neuron_type.to_name()
+            ));
+    };
+    if let Some((dna_idx, layer_idx, _)) = selected_gene_idx_from_path(path) {
+        if let Some(layer) = dna
+            .dna
+            .get(dna_idx)
+            .and_then(|dna_block| dna_block.genes.get(layer_idx))
+        {
+            for gene in &layer.genes {
+                add(gene.index, &gene.decoded);
+            }
+        }
+    } else {
+        for cell in dna.cells.iter() {
+            add(cell.index, &cell.decoded);
+        }
+    }
+    carriers
+}
+
+enum OutputTagEdit {
+    MoveUp(usize),
+    MoveDown(usize),
+    Remove(usize),
+}
+
+fn output_tags_ui(ui: &mut Ui, output_tags: &mut Vec<OutputTag>, carriers: &TagCarriers) {
+    ui.horizontal(|ui| {
+        ui.label(RichText::new("Output Tags").strong());
+        if ui.small_button("Add").clicked() {
+            let tag = carriers
+                .keys()
+                .next()
+                .and_then(|&raw| PropertyValue::new(raw))
+                .unwrap_or_default();
+            output_tags.push(OutputTag {
+                tag,
+                weight: PropertyValue::from_weight(0.0).unwrap_or_default(),
+            });
+        }
+    });
+    if output_tags.is_empty() {
         ui.label("None");
         return;
     }
 
+    let mut edit = None;
+    let count = output_tags.len();
     egui::Grid::new("decoded_output_tags_grid")
         .striped(true)
-        .num_columns(3)
+        .num_columns(5)
         .show(ui, |ui| {
-            for (idx, output_tag) in gene_info.output_tags.iter_mut().enumerate() {
+            for (idx, output_tag) in output_tags.iter_mut().enumerate() {
                 ui.label(format!("#{idx:02}"));
-                let output_tag_text = property_char(output_tag.tag);
-                property_value_ui(
-                    ui,
-                    format!("decoded_output_tag_{idx}"),
-                    &mut output_tag.tag,
-                    output_tag_text,
-                );
-                let output_weight_text = format!("{:.3}", output_tag.weight.as_weight());
+                ui.horizontal(|ui| {
+                    property_value_ui(
+                        ui,
+                        format!("decoded_output_tag_{idx}"),
+                        &mut output_tag.tag,
+                        PChar,
+                    );
+                    output_target_combo(ui, idx, &mut output_tag.tag, carriers);
+                });
                 property_value_ui(
                     ui,
                     format!("decoded_output_weight_{idx}"),
                     &mut output_tag.weight,
-                    output_weight_text,
+                    PWeight,
                 );
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(idx > 0, Button::new("⏶").small()).clicked() {
+                        edit = Some(OutputTagEdit::MoveUp(idx));
+                    }
+                    if ui
+                        .add_enabled(idx + 1 < count, Button::new("⏷").small())
+                        .clicked()
+                    {
+                        edit = Some(OutputTagEdit::MoveDown(idx));
+                    }
+                    if ui.small_button("🗑").on_hover_text("Remove").clicked() {
+                        edit = Some(OutputTagEdit::Remove(idx));
+                    }
+                });
+                if carriers.contains_key(&output_tag.tag.raw()) {
+                    ui.label("");
+                } else {
+                    ui.colored_label(ui.visuals().warn_fg_color, "⚠ no cell has this tag");
+                }
                 ui.end_row();
             }
         });
+
+    match edit {
+        Some(OutputTagEdit::MoveUp(idx)) => output_tags.swap(idx - 1, idx),
+        Some(OutputTagEdit::MoveDown(idx)) => output_tags.swap(idx, idx + 1),
+        Some(OutputTagEdit::Remove(idx)) => {
+            output_tags.remove(idx);
+        }
+        None => {}
+    }
+}
+
+fn output_target_combo(ui: &mut Ui, idx: usize, tag: &mut PropertyValue, carriers: &TagCarriers) {
+    egui::ComboBox::from_id_salt(("output_target", idx))
+        .selected_text("")
+        .width(16.0)
+        .show_ui(ui, |ui| {
+            if carriers.is_empty() {
+                ui.label("No tags in this creature.");
+            }
+            for (&raw, cells) in carriers {
+                let Some(target) = PropertyValue::new(raw) else {
+                    continue;
+                };
+                let mut label = format!(
+                    "{}  {}",
+                    property_char(target),
+                    cells[..cells.len().min(3)].join(", ")
+                );
+                if cells.len() > 3 {
+                    label.push_str(&format!(" +{} more", cells.len() - 3));
+                }
+                ui.selectable_value(tag, target, label);
+            }
+        });
+}
+
+/// Color picker plus one 0..63 slider per channel. Returns true if the color was changed.
+fn channel_color_ui<T: std::hash::Hash>(
+    ui: &mut Ui,
+    id_source: T,
+    color: &mut ChannelColor,
+) -> bool {
+    ui.push_id(id_source, |ui| {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            let mut rgb = color.to_rgb8();
+            if egui::color_picker::color_edit_button_srgb(ui, &mut rgb).changed() {
+                *color = ChannelColor::from_rgb8(rgb);
+                changed = true;
+            }
+            ui.monospace(color.to_letters());
+        });
+        for (name, channel) in ["R", "G", "B"].into_iter().zip(color.0.iter_mut()) {
+            changed |= ui.add(value_slider(channel).text(name)).changed();
+        }
+        changed
+    })
+    .inner
 }
 
 fn string_edit_widget<F>(ui: &mut Ui, description: &str, value: &str, mut on_edit: F)
@@ -531,6 +1625,23 @@ where
 fn selected_string_entry_ui(ui: &mut Ui, dna: &mut CreatureDNA, path: &str) -> bool {
     let parts: Vec<&str> = path.split('/').collect();
     match parts.as_slice() {
+        ["CreatureDNA", "creature", "skin_color"] => {
+            let current = dna.creature.skin_color.clone().unwrap_or_default();
+            string_edit_widget(ui, "Skin Color", &current, |new_value| {
+                dna.creature.skin_color = Some(new_value.to_owned());
+            });
+            match dna.creature.skin_color() {
+                Some(mut color) => {
+                    if channel_color_ui(ui, "skin_color", &mut color) {
+                        dna.creature.set_skin_color(color);
+                    }
+                }
+                None => {
+                    ui.label("Expected three channel letters, e.g. aHa.");
+                }
+            }
+            true
+        }
         ["CreatureDNA", "dna", block_idx, "dna_name"] => {
             let Some(block_idx) = block_idx.parse::<usize>().ok() else {
                 return false;
@@ -615,21 +1726,130 @@ fn selected_decoded_info_mut<'a>(
     }
 }
 
+/// Text field taking the decoded value or a letter of the encoding, plus a slider for numeric
+/// values. Up/down step the value while the text field has focus, the wheel while hovered.
 fn property_value_ui<T: std::hash::Hash>(
     ui: &mut Ui,
     id_source: T,
     value: &mut PropertyValue,
-    text: String,
+    representation: PropertyValueRepresentation,
 ) {
-    let response = ui
-        .push_id(id_source, |ui| {
-            ui.add(egui::Label::new(text).sense(egui::Sense::hover()))
+    ui.push_id(id_source, |ui| {
+        ui.horizontal(|ui| {
+            let response = value_text_edit(ui, value, representation);
+            if response.hovered() && !response.has_focus() {
+                apply_scroll_step(ui, value);
+            }
+            response.on_hover_text(format!(
+                "Letter {}, value {}. Type a value or scroll to increase/decrease",
+                property_char(*value),
+                value.as_int()
+            ));
+
+            if !matches!(representation, PChar | PMirror) {
+                ui.spacing_mut().slider_width = 96.0;
+                ui.add(value_slider(value).show_value(false));
+            }
+        });
+    });
+}
+
+/// Slider over the 64 values of a property.
+fn value_slider(value: &mut PropertyValue) -> egui::Slider<'_> {
+    egui::Slider::from_get_set(0.0..=f64::from(PropertyValue::MAX), |set| {
+        if let Some(set) = set.and_then(|set| PropertyValue::new(set.round() as u8)) {
+            *value = set;
+        }
+        f64::from(value.raw())
+    })
+    .integer()
+}
+
+fn value_text_edit(
+    ui: &mut Ui,
+    value: &mut PropertyValue,
+    representation: PropertyValueRepresentation,
+) -> egui::Response {
+    // While the field has focus the typed text is kept in memory, so partial input such as
+    // "-1." is not reformatted every frame.
+    let id = ui.id().with("value_text");
+    let mut text = ui
+        .data(|data| data.get_temp::<String>(id))
+        .unwrap_or_else(|| value.display(representation));
+    let valid = PropertyValue::parse(&text, representation).is_some();
+    // Focus is locked so Tab reaches `detail_ui`, which moves on to the next value field.
+    let mut edit = egui::TextEdit::singleline(&mut text)
+        .id(id)
+        .desired_width(56.0)
+        .lock_focus(true);
+    if !valid {
+        edit = edit.text_color(ui.visuals().error_fg_color);
+    }
+    let response = ui.add(edit);
+
+    let tab = response.has_focus().then(|| {
+        ui.input_mut(|i| {
+            if i.consume_key(Modifiers::NONE, Key::Tab) {
+                Some(true)
+            } else if i.consume_key(Modifiers::SHIFT, Key::Tab) {
+                Some(false)
+            } else {
+                None
+            }
         })
-        .inner;
-    if response.hovered() {
-        apply_scroll_step(ui, value);
+    });
+    ui.data_mut(|data| {
+        let fields = data.get_temp_mut_or_default::<ValueFields>(value_fields_id());
+        fields.ids.push(id);
+        if let Some(Some(forward)) = tab {
+            fields.tab_from = Some((id, forward));
+        }
+    });
+
+    if response.has_focus() {
+        let (up, down) = ui.input_mut(|i| {
+            (
+                i.consume_key(Modifiers::NONE, Key::ArrowUp),
+                i.consume_key(Modifiers::NONE, Key::ArrowDown),
+            )
+        });
+        if up || down {
+            if let Some(parsed) = PropertyValue::parse(&text, representation) {
+                *value = parsed;
+            }
+            if up {
+                value.increase();
+            } else {
+                value.decrease();
+            }
+            text = value.display(representation);
+        }
+        ui.data_mut(|data| data.insert_temp(id, text));
+    } else {
+        ui.data_mut(|data| data.remove::<String>(id));
+        if response.lost_focus()
+            && let Some(parsed) = PropertyValue::parse(&text, representation)
+        {
+            *value = parsed;
+        }
     }
-    response.on_hover_text("Scroll to increase/decrease");
+    response
+}
+
+/// Mirroring mode, or none to leave out the `_` field (which the game reads as `P`).
+fn mirroring_combo(ui: &mut Ui, mirroring: &mut Option<GeneMirroring>) {
+    let selected_text = match mirroring {
+        Some(mirroring) => mirroring.0.as_mirror(),
+        None => "none (P)",
+    };
+    egui::ComboBox::from_id_salt("mirroring_combo")
+        .selected_text(selected_text)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(mirroring, None, "none (P)");
+            for option in PropertyValue::mirror_modes() {
+                ui.selectable_value(mirroring, Some(GeneMirroring(option)), option.as_mirror());
+            }
+        });
 }
 
 fn apply_scroll_step(ui: &Ui, value: &mut PropertyValue) {
@@ -683,3 +1903,67 @@ fn section_with_id<T, I, F>(
         .default_open(default_open)
         .show(ui, add_contents);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tag_carriers_of_cells_and_layers() {
+        let dna = crate::read_creature("data/e5.txt").expect("failed to read data/e5.txt");
+        let carriers = tag_carriers(&dna, "CreatureDNA/cells/0");
+        let listed: usize = carriers.values().map(Vec::len).sum();
+        assert_eq!(listed, dna.cells.len());
+        let first = &dna.cells[0];
+        assert!(carriers[&first.decoded.tag.0.raw()].contains(&format!(
+            "[{}][{}] {}",
+            first.index.x,
+            first.index.y,
+            first.decoded.neuron_type.to_name()
+        )));
+
+        let layer = &dna.dna[0].genes[0];
+        let carriers = tag_carriers(&dna, "CreatureDNA/dna/0/genes/0/0");
+        let listed: usize = carriers.values().map(Vec::len).sum();
+        assert_eq!(listed, layer.genes.len());
+    }
+
+    #[test]
+    fn palette_is_stored_as_letters() {
+        let palette = NeuronPalette::default();
+        let letters = String::from(palette);
+        assert_eq!(letters.chars().count(), 10);
+        assert_eq!(NeuronPalette::try_from(letters), Ok(palette));
+        assert!(NeuronPalette::try_from("abc".to_owned()).is_err());
+        assert!(NeuronPalette::try_from("abcdefghi#".to_owned()).is_err());
+    }
+
+    #[test]
+    fn grid_records_are_found_and_inserted_at_the_cursor() {
+        let mut dna = crate::read_creature("data/e5.txt").expect("failed to read data/e5.txt");
+        let first = dna.dna[0].genes[0].genes[0].index;
+        let mut selected_path = None;
+        let mut cursor = None;
+        let mut grid = CellGrid {
+            dna: &mut dna,
+            selected_path: &mut selected_path,
+            cursor: &mut cursor,
+            mode: CellGridMode::DnaGenesLayer {
+                dna_idx: 0,
+                layer_idx: 0,
+            },
+            size: 9,
+            highlights: HashSet::new(),
+            marked: &mut HashSet::new(),
+        };
+        assert_eq!(grid.record_at(first), Some(0));
+        assert_eq!(grid.record_path(0), "CreatureDNA/dna/0/genes/0/0");
+
+        let free = GridIndex2 { x: 200, y: 200 };
+        assert_eq!(grid.record_at(free), None);
+        let idx = grid.insert_record(free).expect("layer exists");
+        assert_eq!(grid.record_at(free), Some(idx));
+        *grid.selected_path = Some(grid.record_path(idx));
+        assert_eq!(grid.selected_record(), Some(idx));
+    }
+}
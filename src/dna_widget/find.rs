@@ -0,0 +1,239 @@
+use egui::{ComboBox, Grid, RichText, ScrollArea, Ui};
+
+use super::{neuron_type_picker, property_value_ui};
+use crate::dnaparser::PropertyValueRepresentation::{PChar, PInt};
+use crate::dnaparser::{CreatureDNA, NeuronType, PropertyValue};
+use crate::merge::RecordLocation;
+use crate::search::{self, Criterion, Replacement};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Field {
+    #[default]
+    NeuronKind,
+    Tag,
+    Property,
+    OutputTarget,
+    Encoded,
+}
+
+impl Field {
+    const ALL: [Field; 5] = [
+        Field::NeuronKind,
+        Field::Tag,
+        Field::Property,
+        Field::OutputTarget,
+        Field::Encoded,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Field::NeuronKind => "neuron kind",
+            Field::Tag => "tag",
+            Field::Property => "property",
+            Field::OutputTarget => "output target",
+            Field::Encoded => "encoded text",
+        }
+    }
+}
+
+/// A criterion or replacement as edited in the panel.
+#[derive(Default)]
+struct FieldValue {
+    field: Field,
+    neuron_type: NeuronType,
+    /// Property index for `Field::Property`.
+    property: usize,
+    value: PropertyValue,
+    text: String,
+    /// Output target or text that a replacement replaces.
+    from_value: PropertyValue,
+    from_text: String,
+}
+
+impl FieldValue {
+    fn ui(&mut self, ui: &mut Ui, id_salt: &str, replacing: bool) {
+        ui.push_id(id_salt, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                ComboBox::from_id_salt("field")
+                    .selected_text(self.field.name())
+                    .show_ui(ui, |ui| {
+                        for field in Field::ALL {
+                            ui.selectable_value(&mut self.field, field, field.name());
+                        }
+                    });
+                match self.field {
+                    Field::NeuronKind => {
+                        if let Some(choice) = neuron_type_picker(ui, self.neuron_type) {
+                            self.neuron_type = choice.neuron_type;
+                        }
+                    }
+                    Field::Tag => property_value_ui(ui, "tag", &mut self.value, PChar),
+                    Field::Property => {
+                        ComboBox::from_id_salt("property")
+                            .selected_text(format!("#{}", self.property))
+                            .width(48.0)
+                            .show_ui(ui, |ui| {
+                                for index in 0..8 {
+                                    ui.selectable_value(
+                                        &mut self.property,
+                                        index,
+                                        format!("#{index}"),
+                                    );
+                                }
+                            });
+                        ui.label("=");
+                        property_value_ui(ui, "property_value", &mut self.value, PInt);
+                    }
+                    Field::OutputTarget => {
+                        if replacing {
+                            property_value_ui(ui, "from", &mut self.from_value, PChar);
+                            ui.label("→");
+                        }
+                        property_value_ui(ui, "target", &mut self.value, PChar);
+                    }
+                    Field::Encoded => {
+                        if replacing {
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.from_text)
+                                    .hint_text("text")
+                                    .desired_width(80.0),
+                            );
+                            ui.label("→");
+                        }
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.text)
+                                .hint_text(if replacing {
+                                    "replacement"
+                                } else {
+                                    "e.g. *e or J!"
+                                })
+                                .desired_width(80.0),
+                        );
+                    }
+                }
+            });
+        });
+    }
+
+    fn criterion(&self) -> Criterion {
+        match self.field {
+            Field::NeuronKind => Criterion::NeuronKind(self.neuron_type),
+            Field::Tag => Criterion::Tag(self.value),
+            Field::Property => Criterion::Property {
+                index: self.property,
+                value: self.value,
+            },
+            Field::OutputTarget => Criterion::OutputTarget(self.value),
+            Field::Encoded => Criterion::Encoded(self.text.clone()),
+        }
+    }
+
+    fn replacement(&self) -> Replacement {
+        match self.field {
+            Field::NeuronKind => Replacement::NeuronKind(self.neuron_type),
+            Field::Tag => Replacement::Tag(self.value),
+            Field::Property => Replacement::Property {
+                index: self.property,
+                value: self.value,
+            },
+            Field::OutputTarget => Replacement::OutputTarget {
+                from: self.from_value,
+                to: self.value,
+            },
+            Field::Encoded => Replacement::Encoded {
+                from: self.from_text.clone(),
+                to: self.text.clone(),
+            },
+        }
+    }
+}
+
+/// State of the Find and Replace panel of one creature.
+#[derive(Default)]
+pub(super) struct FindState {
+    find: FieldValue,
+    replace: FieldValue,
+    /// Records matching the criterion, highlighted in the grids.
+    pub matches: Vec<RecordLocation>,
+    message: Option<String>,
+}
+
+pub(super) fn find_ui(
+    ui: &mut Ui,
+    dna: &mut CreatureDNA,
+    state: &mut FindState,
+    selected_path: &mut Option<String>,
+) {
+    Grid::new("find_replace").num_columns(2).show(ui, |ui| {
+        ui.label("Find");
+        state.find.ui(ui, "find", false);
+        ui.end_row();
+        ui.label("Replace");
+        state.replace.ui(ui, "replace", true);
+        ui.end_row();
+    });
+
+    let criterion = state.find.criterion();
+    ui.horizontal(|ui| {
+        let can_replace = !state.matches.is_empty();
+        if ui
+            .add_enabled(can_replace, egui::Button::new("Replace all matches"))
+            .clicked()
+        {
+            let changed = search::replace(dna, &criterion, &state.replace.replacement());
+            state.message = Some(format!("Changed {changed} record(s)."));
+        }
+        if let Some(message) = state.message.as_deref() {
+            ui.label(message);
+        }
+    });
+
+    state.matches = search::find(dna, &criterion);
+    ui.separator();
+    ui.label(RichText::new(format!("{} match(es)", state.matches.len())).strong());
+    ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+        for location in &state.matches {
+            let Some(path) = path_of(dna, *location) else {
+                continue;
+            };
+            let name = super::selected_decoded_info_mut(dna, &path)
+                .map(|info| info.neuron_type.to_name())
+                .unwrap_or_default();
+            let selected = selected_path.as_deref() == Some(path.as_str());
+            if ui
+                .selectable_label(selected, format!("{location} {name}"))
+                .clicked()
+            {
+                *selected_path = Some(path);
+            }
+        }
+    });
+}
+
+/// Tree path of the record at `location`.
+fn path_of(dna: &CreatureDNA, location: RecordLocation) -> Option<String> {
+    match location {
+        RecordLocation::Cell(index) => {
+            let idx = dna.cells.iter().position(|cell| cell.index == index)?;
+            Some(format!("CreatureDNA/cells/{idx}"))
+        }
+        RecordLocation::Gene {
+            dna_idx,
+            z_level,
+            index,
+        } => {
+            let block = dna.dna.get(dna_idx)?;
+            let layer_idx = block
+                .genes
+                .iter()
+                .position(|layer| layer.z_level == z_level)?;
+            let gene_idx = block.genes[layer_idx]
+                .genes
+                .iter()
+                .position(|gene| gene.index == index)?;
+            Some(format!(
+                "CreatureDNA/dna/{dna_idx}/genes/{layer_idx}/{gene_idx}"
+            ))
+        }
+    }
+}
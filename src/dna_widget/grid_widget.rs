@@ -4,12 +4,12 @@ use egui::{LayerId, Painter};
 ///
 /// Render a grid and the creature on it, add bunch of visualizations if requested:
 /// WIP
-use super::super::dnaparser;
-
+#[allow(dead_code)]
 struct DnaGrid {
     painter: Painter,
 }
 
+#[allow(dead_code)]
 impl DnaGrid {
     pub fn new(ctx: egui::Context, layer_id: LayerId, clip_rect: egui::Rect) -> Self {
         DnaGrid {
@@ -0,0 +1,91 @@
+use egui::{Popup, PopupCloseBehavior, RichText, ScrollArea, Ui};
+
+use crate::dnaparser::NeuronType;
+use crate::pdf_infos::{NeuronCategory, neuron_category, property_summary};
+
+/// A neuron type chosen in the picker.
+pub(super) struct NeuronChoice {
+    pub neuron_type: NeuronType,
+    /// Whether the properties should be reset to the defaults of the new kind.
+    pub reset_properties: bool,
+}
+
+#[derive(Clone, Default)]
+struct PickerState {
+    query: String,
+    reset_properties: bool,
+}
+
+/// Button showing the current type that opens a searchable list of all types, grouped by
+/// category.
+pub(super) fn neuron_type_picker(ui: &mut Ui, current: NeuronType) -> Option<NeuronChoice> {
+    let button = ui
+        .button(format!("{} {}", current.to_char(), current.to_name()))
+        .on_hover_text(property_summary(char_of(current)));
+    let state_id = button.id.with("neuron_type_picker_state");
+    let mut choice = None;
+
+    Popup::from_toggle_button_response(&button)
+        .close_behavior(PopupCloseBehavior::CloseOnClickOutside)
+        .width(360.0)
+        .show(|ui| {
+            let mut state: PickerState =
+                ui.data(|data| data.get_temp(state_id)).unwrap_or_default();
+            let search = ui.add(
+                egui::TextEdit::singleline(&mut state.query)
+                    .hint_text("Search name, letter or property")
+                    .desired_width(f32::INFINITY),
+            );
+            if ui.memory(|memory| memory.focused().is_none()) {
+                search.request_focus();
+            }
+            ui.checkbox(&mut state.reset_properties, "Reset properties to defaults");
+            ui.separator();
+
+            let query = state.query.trim().to_lowercase();
+            let matches = |neuron_type: NeuronType| {
+                let letter = neuron_type.to_char();
+                query.is_empty()
+                    || letter == state.query.trim()
+                    || neuron_type.to_name().contains(&query)
+                    || property_summary(char_of(neuron_type)).contains(&query)
+            };
+
+            ScrollArea::vertical().max_height(360.0).show(ui, |ui| {
+                for category in NeuronCategory::ALL {
+                    let types: Vec<NeuronType> = NeuronType::all()
+                        .filter(|neuron_type| neuron_category(char_of(*neuron_type)) == category)
+                        .filter(|neuron_type| matches(*neuron_type))
+                        .collect();
+                    if types.is_empty() {
+                        continue;
+                    }
+                    ui.label(RichText::new(category.name()).strong());
+                    for neuron_type in types {
+                        let label = format!("{}  {}", neuron_type.to_char(), neuron_type.to_name());
+                        let response = ui.selectable_label(neuron_type == current, label);
+                        ui.label(
+                            RichText::new(property_summary(char_of(neuron_type)))
+                                .small()
+                                .weak(),
+                        );
+                        if response.clicked() {
+                            choice = Some(NeuronChoice {
+                                neuron_type,
+                                reset_properties: state.reset_properties,
+                            });
+                            ui.close();
+                        }
+                    }
+                }
+            });
+
+            ui.data_mut(|data| data.insert_temp(state_id, state));
+        });
+
+    choice.filter(|choice| choice.neuron_type != current || choice.reset_properties)
+}
+
+fn char_of(neuron_type: NeuronType) -> char {
+    neuron_type.0.to_char().unwrap_or('?')
+}
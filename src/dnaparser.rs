@@ -133,7 +133,8 @@ impl CreatureDNA {
         Ok(out)
     }
 
-    pub fn to_text(&self) -> String {
+    /// Fails if a gene can't be encoded.
+    pub fn to_text(&self) -> Result<String> {
         let mut out = String::with_capacity(1024 + self.cells.len() * 96 + self.dna.len() * 256);
 
         out.push_str("/////////////////////////////////////////////////////////////////////////////////////\n");
@@ -156,12 +157,14 @@ impl CreatureDNA {
 
         out.push_str("//cells: \n");
         for cell in self.cells.iter() {
+            let encoded = cell
+                .decoded
+                .encode()
+                .map_err(|err| format!("cell [{}][{}]: {err}", cell.index.x, cell.index.y))?;
             let _ = writeln!(
                 out,
-                "neuron_properties[{}][{}] = {}",
-                cell.index.x,
-                cell.index.y,
-                cell.decoded.encode()
+                "neuron_properties[{}][{}] = {encoded}",
+                cell.index.x, cell.index.y
             );
         }
         out.push('\n');
@@ -195,13 +198,16 @@ impl CreatureDNA {
 
             for layer in dna.genes.iter() {
                 for gene in &layer.genes {
+                    let encoded = gene.decoded.encode().map_err(|err| {
+                        format!(
+                            "gene [{}][{}][{}]: {err}",
+                            gene.index.x, gene.index.y, layer.z_level
+                        )
+                    })?;
                     let _ = writeln!(
                         out,
-                        "gene[{}][{}][{}] = {}",
-                        gene.index.x,
-                        gene.index.y,
-                        layer.z_level,
-                        gene.decoded.encode()
+                        "gene[{}][{}][{}] = {encoded}",
+                        gene.index.x, gene.index.y, layer.z_level
                     );
                 }
             }
@@ -215,7 +221,7 @@ impl CreatureDNA {
             }
         }
 
-        out
+        Ok(out)
     }
 }
 
@@ -311,7 +317,18 @@ pub struct CreatureData {
     pub skin_color: Option<String>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+impl CreatureData {
+    /// `skin_color` as a color, `None` if it is missing or not three channel letters.
+    pub fn skin_color(&self) -> Option<ChannelColor> {
+        ChannelColor::from_letters(self.skin_color.as_deref()?)
+    }
+
+    pub fn set_skin_color(&mut self, color: ChannelColor) {
+        self.skin_color = Some(color.to_letters());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
 pub struct GridIndex2 {
     pub x: u16,
     pub y: u16,
@@ -339,6 +356,86 @@ pub struct DnaData {
     pub genes: DnaGenes,
 }
 
+impl DnaData {
+    /// Length of the ids the game uses as `dna_name`.
+    pub const NAME_LEN: usize = 9;
+
+    /// An empty block with a single layer, placed at `location`.
+    pub fn new(name: String, creator: String, location: Option<GridIndex2>) -> Self {
+        let mut block = Self {
+            dna_comment_name: Some(name.clone()),
+            dna_name: Some(DnaNameRecord {
+                index: GridIndex2::default(),
+                name,
+            }),
+            dna_location: None,
+            dna_creator: Some(DnaCreatorRecord {
+                index: GridIndex2::default(),
+                creator,
+            }),
+            genes: DnaGenes::default(),
+        };
+        block.genes.add_layer();
+        block.place_at(location);
+        block
+    }
+
+    /// Moves the location and the name and creator records to a cell. `None` only removes the
+    /// location.
+    pub fn place_at(&mut self, location: Option<GridIndex2>) {
+        self.dna_location = location;
+        let Some(index) = location else {
+            return;
+        };
+        if let Some(record) = self.dna_name.as_mut() {
+            record.index = index;
+        }
+        if let Some(record) = self.dna_creator.as_mut() {
+            record.index = index;
+        }
+    }
+
+    /// Changes `dna_name`, and the comment name if it was the same.
+    pub fn rename(&mut self, name: String) {
+        if let Some(record) = self.dna_name.as_mut() {
+            if self.dna_comment_name.as_deref() == Some(record.name.as_str()) {
+                self.dna_comment_name = Some(name.clone());
+            }
+            record.name = name;
+        } else {
+            self.dna_name = Some(DnaNameRecord {
+                index: self.dna_location.unwrap_or_default(),
+                name,
+            });
+        }
+    }
+
+    /// A random id like the ones the game generates, different from all `taken` names.
+    pub fn generate_name<'a>(taken: impl IntoIterator<Item = &'a str> + Clone) -> String {
+        use std::hash::{BuildHasher, Hasher};
+        let state = std::hash::RandomState::new();
+        let mut attempt = 0u64;
+        loop {
+            attempt += 1;
+            let mut hasher = state.build_hasher();
+            hasher.write_u64(attempt);
+            let mut bits = hasher.finish();
+            let name: String = (0..Self::NAME_LEN)
+                .filter_map(|_| {
+                    let value = PropertyValue {
+                        raw: (bits % 64) as u8,
+                    };
+                    bits /= 64;
+                    value.to_char()
+                })
+                .collect();
+            if !taken.clone().into_iter().any(|other| other == name) {
+                return name;
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DnaNameRecord {
     pub index: GridIndex2,
@@ -382,6 +479,55 @@ impl DnaGenes {
         self.0.iter().map(|layer| layer.genes.len()).sum()
     }
 
+    /// One above the highest z-level in use, 0 if there are no layers.
+    pub fn next_free_z_level(&self) -> u16 {
+        self.0
+            .iter()
+            .map(|layer| layer.z_level.saturating_add(1))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Appends an empty layer with a free z-level and returns its index.
+    pub fn add_layer(&mut self) -> usize {
+        let z_level = self.next_free_z_level();
+        self.0.push(DnaLayer {
+            z_level,
+            genes: Vec::new(),
+        });
+        self.0.len() - 1
+    }
+
+    /// Inserts a copy of a layer with a free z-level right after it and returns its index.
+    pub fn duplicate_layer(&mut self, layer_idx: usize) -> Option<usize> {
+        let mut copy = self.0.get(layer_idx)?.clone();
+        copy.z_level = self.next_free_z_level();
+        self.0.insert(layer_idx + 1, copy);
+        Some(layer_idx + 1)
+    }
+
+    /// Moves a layer to `z_level`, skipping past levels other layers use in the direction of
+    /// the change so that two layers never share one. Returns the level the layer ends up on.
+    pub fn set_z_level(&mut self, layer_idx: usize, z_level: u16) -> Option<u16> {
+        let current = self.0.get(layer_idx)?.z_level;
+        let taken = |z_level: u16| {
+            self.0
+                .iter()
+                .enumerate()
+                .any(|(idx, layer)| idx != layer_idx && layer.z_level == z_level)
+        };
+        let mut free = z_level;
+        while taken(free) {
+            free = if z_level < current {
+                free.checked_sub(1)?
+            } else {
+                free.checked_add(1)?
+            };
+        }
+        self.0[layer_idx].z_level = free;
+        Some(free)
+    }
+
     pub fn get_layer_gene_mut(
         &mut self,
         layer_idx: usize,
@@ -415,29 +561,40 @@ pub struct DecodedGeneInfo {
     pub properties: [GeneProperty; 8],
     pub bias: GeneBias,
     pub ampersand: Option<GeneProperty>,
-    pub mirroring: GeneMirroring,
+    /// `None` if the gene has no `_` field. The game reads that like `P`, but files written
+    /// by it often spell `_A` out, so both are kept apart to write files back unchanged.
+    pub mirroring: Option<GeneMirroring>,
     pub output_tags: Vec<OutputTag>,
 }
 
 impl DecodedGeneInfo {
-    pub fn encode(&self) -> String {
+    pub fn encode(&self) -> Result<String> {
         let mut out = String::with_capacity(16 + self.output_tags.len() * 3);
-        self.neuron_type.encode(&mut out);
-        self.tag.encode(&mut out);
+        self.neuron_type.encode(&mut out)?;
+        self.tag.encode(&mut out)?;
         for (idx, prop) in self.properties.iter().enumerate() {
-            prop.encode(idx, &mut out);
+            prop.encode(idx, &mut out)?;
         }
-        self.bias.encode(&mut out);
+        self.bias.encode(&mut out)?;
         if let Some(ampersand) = self.ampersand {
-            ampersand.encode_ampersand(&mut out);
+            ampersand.encode_ampersand(&mut out)?;
         }
-        if self.mirroring.0.raw != 0 {
-            self.mirroring.encode(&mut out);
+        if let Some(mirroring) = self.mirroring {
+            mirroring.encode(&mut out)?;
         }
         for output_tag in &self.output_tags {
-            output_tag.encode(&mut out);
+            output_tag.encode(&mut out)?;
+        }
+        Ok(out)
+    }
+
+    /// Inverse of `encode`.
+    pub fn decode(encoded: &str) -> Result<Self> {
+        match parser::decode_gene_info(encoded) {
+            Ok(("", info)) => Ok(info),
+            Ok(_) => Err(format!("trailing data in gene encoding '{encoded}'")),
+            Err(_) => Err(format!("invalid gene encoding '{encoded}'")),
         }
-        out
     }
 }
 
@@ -468,9 +625,47 @@ pub struct GeneBias(pub PropertyValue);
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct GeneMirroring(pub PropertyValue);
 
+/// Red, green and blue channels of 0..63 each, as used by `skin_color` and the color
+/// properties of pigment cells, lamps, blinkers and membrane makers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ChannelColor(pub [PropertyValue; 3]);
+
+/// One of the 64 values of a gene field, written as one of the letters `A-Za-z0-9?!`. Out of
+/// range values can't be constructed, deserialized included.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "RawPropertyValue")]
 pub struct PropertyValue {
-    pub raw: u8,
+    raw: u8,
+}
+
+/// Serialized form of `PropertyValue`, checked before it becomes one.
+#[derive(Deserialize)]
+struct RawPropertyValue {
+    raw: u8,
+}
+
+impl TryFrom<RawPropertyValue> for PropertyValue {
+    type Error = String;
+
+    fn try_from(value: RawPropertyValue) -> Result<Self> {
+        Self::try_from(value.raw)
+    }
+}
+
+impl TryFrom<u8> for PropertyValue {
+    type Error = String;
+
+    fn try_from(raw: u8) -> Result<Self> {
+        Self::new(raw).ok_or_else(|| format!("value {raw} out of range 0..63"))
+    }
+}
+
+impl TryFrom<char> for PropertyValue {
+    type Error = String;
+
+    fn try_from(c: char) -> Result<Self> {
+        Self::from_char(c).ok_or_else(|| format!("'{c}' is not one of A-Za-z0-9?!"))
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -493,6 +688,7 @@ pub enum PropertyTag {
     PTOutputTag, // [
 }
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum PropertyValueRepresentation {
     #[default]
@@ -502,6 +698,7 @@ pub enum PropertyValueRepresentation {
     PWeight,
     PBias,
     PMirror,
+    PChar,
 }
 
 impl NeuronType {
@@ -583,14 +780,32 @@ impl NeuronType {
         Self::NAME_MAP.get(idx).copied().unwrap_or("invalid")
     }
 
+    /// Inverse of `to_name`. Names that are not unique ("unknown") resolve to their first use.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAME_MAP
+            .iter()
+            .position(|mapped| mapped.eq_ignore_ascii_case(name))
+            .map(|idx| Self(PropertyValue { raw: idx as u8 }))
+    }
+
+    /// The cell that holds DNA blocks.
+    pub fn is_dna(self) -> bool {
+        self.to_name() == "dna"
+    }
+
+    /// Whether `to_name` identifies this type without ambiguity.
+    pub fn has_unique_name(self) -> bool {
+        Self::from_name(self.to_name()) == Some(self)
+    }
+
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0..Self::NAME_MAP.len() as u8).map(|raw| Self(PropertyValue { raw }))
+    }
+
     #[inline]
-    fn encode(self, out: &mut String) {
+    fn encode(self, out: &mut String) -> Result<()> {
         out.push('*');
-        out.push(
-            self.0
-                .to_char()
-                .expect("invalid PropertyValue for NeuronType"),
-        );
+        self.0.encode("neuron type", out)
     }
 }
 
@@ -601,9 +816,9 @@ impl GeneTag {
     }
 
     #[inline]
-    fn encode(self, out: &mut String) {
+    fn encode(self, out: &mut String) -> Result<()> {
         out.push('$');
-        out.push(self.0.to_char().expect("invalid PropertyValue for GeneTag"));
+        self.0.encode("tag", out)
     }
 }
 
@@ -637,24 +852,16 @@ impl GeneProperty {
     }
 
     #[inline]
-    fn encode(self, index: usize, out: &mut String) {
+    fn encode(self, index: usize, out: &mut String) -> Result<()> {
         const PROPERTY_TAGS: [char; 8] = ['#', '@', '%', '^', '+', '|', '{', '}'];
         out.push(PROPERTY_TAGS[index]);
-        out.push(
-            self.0
-                .to_char()
-                .expect("invalid PropertyValue for GeneProperty"),
-        );
+        self.0.encode(&format!("property {index}"), out)
     }
 
     #[inline]
-    fn encode_ampersand(self, out: &mut String) {
+    fn encode_ampersand(self, out: &mut String) -> Result<()> {
         out.push('&');
-        out.push(
-            self.0
-                .to_char()
-                .expect("invalid PropertyValue for GeneProperty"),
-        );
+        self.0.encode("ampersand", out)
     }
 }
 
@@ -665,13 +872,9 @@ impl GeneBias {
     }
 
     #[inline]
-    fn encode(self, out: &mut String) {
+    fn encode(self, out: &mut String) -> Result<()> {
         out.push('~');
-        out.push(
-            self.0
-                .to_char()
-                .expect("invalid PropertyValue for GeneBias"),
-        );
+        self.0.encode("bias", out)
     }
 }
 
@@ -682,13 +885,9 @@ impl GeneMirroring {
     }
 
     #[inline]
-    fn encode(self, out: &mut String) {
+    fn encode(self, out: &mut String) -> Result<()> {
         out.push('_');
-        out.push(
-            self.0
-                .to_char()
-                .expect("invalid PropertyValue for GeneMirroring"),
-        );
+        self.0.encode("mirroring", out)
     }
 }
 
@@ -704,18 +903,38 @@ impl OutputTag {
     }
 
     #[inline]
-    fn encode(self, out: &mut String) {
+    fn encode(self, out: &mut String) -> Result<()> {
         out.push('[');
-        out.push(
-            self.tag
-                .to_char()
-                .expect("invalid PropertyValue for OutputTag.tag"),
-        );
-        out.push(
-            self.weight
-                .to_char()
-                .expect("invalid PropertyValue for OutputTag.weight"),
-        );
+        self.tag.encode("output tag", out)?;
+        self.weight.encode("output weight", out)
+    }
+}
+
+impl ChannelColor {
+    pub fn from_letters(letters: &str) -> Option<Self> {
+        let mut chars = letters.chars();
+        let mut channel = || chars.next().and_then(PropertyValue::from_char);
+        let color = Self([channel()?, channel()?, channel()?]);
+        chars.next().is_none().then_some(color)
+    }
+
+    pub fn to_letters(self) -> String {
+        self.0
+            .iter()
+            .filter_map(|channel| channel.to_char())
+            .collect()
+    }
+
+    /// Scales the channels to 0..255, mapping 63 to 255.
+    pub fn to_rgb8(self) -> [u8; 3] {
+        self.0.map(|channel| channel.raw * 4 + channel.raw / 16)
+    }
+
+    /// Nearest color the game can store.
+    pub fn from_rgb8(rgb: [u8; 3]) -> Self {
+        Self(rgb.map(|value| PropertyValue {
+            raw: ((u16::from(value) * 63 + 127) / 255) as u8,
+        }))
     }
 }
 
@@ -723,6 +942,33 @@ impl PropertyValue {
     const VALUE_MAP: &'static [u8; 64] =
         b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789?!";
 
+    pub const MAX: u8 = 63;
+
+    /// `None` if `raw` is above `MAX`.
+    pub const fn new(raw: u8) -> Option<Self> {
+        if raw <= Self::MAX {
+            Some(Self { raw })
+        } else {
+            None
+        }
+    }
+
+    pub const fn raw(self) -> u8 {
+        self.raw
+    }
+
+    pub fn saturating_add(self, steps: u8) -> Self {
+        Self {
+            raw: self.raw.saturating_add(steps).min(Self::MAX),
+        }
+    }
+
+    pub fn saturating_sub(self, steps: u8) -> Self {
+        Self {
+            raw: self.raw.saturating_sub(steps),
+        }
+    }
+
     pub fn from_char(c: char) -> Option<Self> {
         if !c.is_ascii() {
             return None;
@@ -769,15 +1015,85 @@ impl PropertyValue {
         Self::MIRROR_MAP[(usize::from(self.raw)) % Self::MIRROR_MAP.len()]
     }
 
-    pub fn increase(&mut self) {
-        if self.raw < 63 {
-            self.raw += 1;
+    // Inverses of the decoded representations. Decimal values snap to the nearest of the 64
+    // steps, so values produced by `as_threshold`/`as_weight` map back to the same raw value.
+    pub fn from_int(value: u8) -> Option<Self> {
+        Self::new(value)
+    }
+    pub fn from_threshold(value: f32) -> Option<Self> {
+        Self::from_scaled(value / 2.5 * 63.0)
+    }
+    pub fn from_weight(value: f32) -> Option<Self> {
+        Self::from_scaled((value + 2.5) / 5.0 * 63.0)
+    }
+    pub fn mirror_modes() -> impl Iterator<Item = Self> {
+        (0..Self::MIRROR_MAP.len() as u8).map(|raw| Self { raw })
+    }
+    pub fn from_mirror(name: &str) -> Option<Self> {
+        Self::MIRROR_MAP
+            .iter()
+            .position(|mapped| mapped.eq_ignore_ascii_case(name))
+            .map(|idx| Self { raw: idx as u8 })
+    }
+    fn from_scaled(scaled: f32) -> Option<Self> {
+        let rounded = scaled.round();
+        (0.0..=63.0)
+            .contains(&rounded)
+            .then_some(Self { raw: rounded as u8 })
+    }
+
+    pub fn display(self, representation: PropertyValueRepresentation) -> String {
+        use PropertyValueRepresentation::*;
+        match representation {
+            PInt => self.as_int().to_string(),
+            PFloat => format!("{:.3}", self.as_float()),
+            PThreshold => format!("{:.3}", self.as_threshold()),
+            PWeight => format!("{:.3}", self.as_weight()),
+            PBias => format!("{:.3}", self.as_bias()),
+            PMirror => self.as_mirror().to_string(),
+            PChar => self.to_char().map(String::from).unwrap_or_default(),
         }
     }
-    pub fn decrease(&mut self) {
-        if self.raw > 0 {
-            self.raw -= 1;
+
+    /// Inverse of `display`. A single letter of the encoding is accepted for every
+    /// representation, unless it reads as a number: `5` is an integer, and out of range as a
+    /// weight rather than the letter `5`.
+    pub fn parse(text: &str, representation: PropertyValueRepresentation) -> Option<Self> {
+        use PropertyValueRepresentation::*;
+        let text = text.trim();
+        let decoded = match representation {
+            PInt => text.parse().ok().and_then(Self::from_int),
+            PFloat => text
+                .parse::<f32>()
+                .ok()
+                .and_then(|value| Self::from_scaled(value * 63.0)),
+            PThreshold | PBias => text.parse().ok().and_then(Self::from_threshold),
+            PWeight => text.parse().ok().and_then(Self::from_weight),
+            PMirror => Self::from_mirror(text),
+            PChar => None,
+        };
+        if decoded.is_some() || (representation != PChar && text.parse::<f32>().is_ok()) {
+            return decoded;
         }
+        let mut chars = text.chars();
+        let c = chars.next()?;
+        chars.next().is_none().then(|| Self::from_char(c)).flatten()
+    }
+
+    pub fn increase(&mut self) {
+        *self = self.saturating_add(1);
+    }
+    pub fn decrease(&mut self) {
+        *self = self.saturating_sub(1);
+    }
+
+    /// Appends the letter of the value; `field` names it in the error.
+    fn encode(self, field: &str, out: &mut String) -> Result<()> {
+        let c = self
+            .to_char()
+            .ok_or_else(|| format!("invalid {field} value {}", self.raw))?;
+        out.push(c);
+        Ok(())
     }
 }
 
@@ -835,7 +1151,7 @@ pub mod parser {
             output_tags: Vec::new(),
             bias: super::GeneBias(PropertyValue::default()),
             ampersand: None,
-            mirroring: super::GeneMirroring(PropertyValue::default()),
+            mirroring: None,
         };
 
         let mut i = input;
@@ -892,7 +1208,7 @@ pub mod parser {
                         PropertyTag::PTAmpersand => {
                             info.ampersand = Some(super::GeneProperty(value))
                         }
-                        PropertyTag::PTMirror => info.mirroring = super::GeneMirroring(value),
+                        PropertyTag::PTMirror => info.mirroring = Some(super::GeneMirroring(value)),
                         PropertyTag::PTOutputTag => {}
                     }
                     i = r;
@@ -908,12 +1224,12 @@ pub mod parser {
         let encoded = "*Y$m#7@0%a^9+3|M{U}M~m&W[vm[gW[cf[b4[vT[Dk[?m[S8[!n[rW[tN[fv[Bu[VQ[4T[wF[Xe[D7[VB[uX[?3[!l";
         let (rest, decoded) = decode_gene_info(encoded).unwrap();
         assert_eq!(rest, "");
-        assert_eq!(decoded.encode(), encoded);
+        assert_eq!(decoded.encode(), Ok(encoded.to_string()));
 
         let encoded = "*e$W#a@U%N^?+c|1{J}R~F&k_n[Ab[IP[S3[?g[mZ[19[KR[eI[3A[2t[ks[qs[Gv[r5[mn[n8[JM[SW[mP[Rz[QJ[WR";
         let (rest, decoded) = decode_gene_info(encoded).unwrap();
         assert_eq!(rest, "");
-        assert_eq!(decoded.encode(), encoded);
+        assert_eq!(decoded.encode(), Ok(encoded.to_string()));
     }
 }
 
@@ -926,7 +1242,7 @@ mod tests {
     fn parse_write_reparse_e5() {
         let input = fs::read_to_string("data/e5.txt").expect("failed to read data/e5.txt");
         let first = parse_creature_dna(&input).expect("failed to parse e5");
-        let serialized = first.to_text();
+        let serialized = first.to_text().expect("failed to write e5");
 
         let mut path = std::env::temp_dir();
         path.push(temp_filename("creature_dna_roundtrip", "txt"));
@@ -948,9 +1264,369 @@ mod tests {
         PathBuf::from(format!("{prefix}_{pid}_{nanos}.{ext}"))
     }
 
+    #[test]
+    fn channel_color_round_trips() {
+        let color = ChannelColor::from_letters("aHa").expect("failed to parse color");
+        assert_eq!(color.0.map(|channel| channel.raw), [26, 7, 26]);
+        assert_eq!(color.to_letters(), "aHa");
+        assert_eq!(ChannelColor::from_rgb8(color.to_rgb8()), color);
+        assert_eq!(
+            ChannelColor::from_letters("!!!").map(ChannelColor::to_rgb8),
+            Some([255; 3])
+        );
+        assert_eq!(ChannelColor::from_letters("aH"), None);
+        assert_eq!(ChannelColor::from_letters("aHaa"), None);
+        assert_eq!(ChannelColor::from_letters("a-a"), None);
+    }
+
+    #[test]
+    fn display_and_parse_are_inverse() {
+        use PropertyValueRepresentation::*;
+        for representation in [PInt, PFloat, PThreshold, PWeight, PBias, PChar] {
+            for raw in 0..64 {
+                let value = PropertyValue { raw };
+                let text = value.display(representation);
+                assert_eq!(PropertyValue::parse(&text, representation), Some(value));
+            }
+        }
+        for value in PropertyValue::mirror_modes() {
+            assert_eq!(
+                PropertyValue::parse(value.as_mirror(), PMirror),
+                Some(value)
+            );
+        }
+
+        assert_eq!(
+            PropertyValue::parse("5", PInt),
+            Some(PropertyValue { raw: 5 })
+        );
+        assert_eq!(
+            PropertyValue::parse("5", PChar),
+            Some(PropertyValue { raw: 57 })
+        );
+        assert_eq!(
+            PropertyValue::parse("J", PWeight),
+            Some(PropertyValue { raw: 9 })
+        );
+        assert_eq!(
+            PropertyValue::parse(" 0 ", PWeight),
+            Some(PropertyValue { raw: 32 })
+        );
+        assert_eq!(PropertyValue::parse("64", PInt), None);
+        assert_eq!(PropertyValue::parse("3", PWeight), None);
+        assert_eq!(PropertyValue::parse("5", PFloat), None);
+        assert_eq!(PropertyValue::parse("3.0", PWeight), None);
+        assert_eq!(PropertyValue::parse("-0.1", PThreshold), None);
+        assert_eq!(PropertyValue::parse("", PChar), None);
+    }
+
+    #[test]
+    fn ampersand_and_mirroring_combinations_round_trip() {
+        let (_, base) = parser::decode_gene_info("*e$W#a@U%N^?+c|1{J}R~F[Ab[IP")
+            .expect("failed to decode gene");
+        let values = || (0..64).map(|raw| PropertyValue { raw });
+        let ampersands = std::iter::once(None).chain(values().map(|v| Some(GeneProperty(v))));
+        for ampersand in ampersands {
+            let mirrorings = std::iter::once(None).chain(values().map(|v| Some(GeneMirroring(v))));
+            for mirroring in mirrorings {
+                let gene = DecodedGeneInfo {
+                    ampersand,
+                    mirroring,
+                    ..base.clone()
+                };
+                let encoded = gene.encode().expect("failed to encode gene");
+                let (rest, decoded) =
+                    parser::decode_gene_info(&encoded).expect("failed to decode encoded gene");
+                assert_eq!(rest, "");
+                assert_eq!(decoded, gene, "{encoded}");
+                assert_eq!(decoded.encode(), Ok(encoded.to_string()));
+            }
+        }
+    }
+
+    #[test]
+    fn explicit_default_mirroring_is_kept() {
+        let encoded = "*e$W#a@U%N^?+c|1{J}R~F_A[Ab";
+        let (_, decoded) = parser::decode_gene_info(encoded).expect("failed to decode gene");
+        assert_eq!(
+            decoded.mirroring,
+            Some(GeneMirroring(PropertyValue { raw: 0 }))
+        );
+        assert_eq!(decoded.encode(), Ok(encoded.to_string()));
+    }
+
+    #[test]
+    fn add_and_duplicate_layers_use_free_z_levels() {
+        let mut genes = DnaGenes::default();
+        assert_eq!(genes.next_free_z_level(), 0);
+        assert_eq!(genes.add_layer(), 0);
+        genes.push_gene(
+            4,
+            GeneRecord {
+                index: GridIndex2 { x: 1, y: 2 },
+                decoded: DecodedGeneInfo::default(),
+            },
+        );
+        assert_eq!(genes.duplicate_layer(0), Some(1));
+        let z_levels: Vec<u16> = genes.iter().map(|layer| layer.z_level).collect();
+        assert_eq!(z_levels, vec![0, 5, 4]);
+        assert_eq!(genes.duplicate_layer(2), Some(3));
+        assert_eq!(genes[3].z_level, 6);
+        assert_eq!(genes[3].genes, genes[2].genes);
+        assert_eq!(genes.duplicate_layer(9), None);
+
+        assert_eq!(genes.set_z_level(0, 4), Some(7));
+        assert_eq!(genes.set_z_level(0, 6), Some(3));
+        assert_eq!(genes.set_z_level(0, 2), Some(2));
+        assert_eq!(genes.set_z_level(1, 4), Some(3));
+        assert_eq!(genes.set_z_level(0, 0), Some(0));
+        assert_eq!(genes.set_z_level(1, 0), None);
+        assert_eq!(genes[1].z_level, 3);
+    }
+
+    #[test]
+    fn new_dna_blocks_are_placed_and_named() {
+        let location = GridIndex2 { x: 3, y: 7 };
+        let name = DnaData::generate_name(["QqGYRoL0A"]);
+        assert_eq!(name.len(), DnaData::NAME_LEN);
+        assert!(name.chars().all(|c| PropertyValue::from_char(c).is_some()));
+
+        let mut block = DnaData::new(name.clone(), "creator".to_string(), Some(location));
+        assert_eq!(block.dna_location, Some(location));
+        assert_eq!(
+            block.dna_name.as_ref().map(|record| record.index),
+            Some(location)
+        );
+        assert_eq!(
+            block.dna_creator.as_ref().map(|record| record.index),
+            Some(location)
+        );
+        assert_eq!(block.genes.len(), 1);
+
+        block.rename("7cHAEuBCF".to_string());
+        assert_eq!(block.dna_comment_name.as_deref(), Some("7cHAEuBCF"));
+        block.place_at(None);
+        assert_eq!(block.dna_location, None);
+        assert_eq!(block.dna_name.map(|record| record.index), Some(location));
+    }
+
+    #[test]
+    fn property_values_stay_in_range() {
+        assert_eq!(PropertyValue::new(63).map(PropertyValue::raw), Some(63));
+        assert_eq!(PropertyValue::new(64), None);
+        assert_eq!(PropertyValue::try_from(9).ok(), PropertyValue::new(9));
+        assert!(PropertyValue::try_from(200u8).is_err());
+        assert_eq!(PropertyValue::try_from('!').map(PropertyValue::raw), Ok(63));
+        assert!(PropertyValue::try_from('-').is_err());
+
+        let value = PropertyValue::new(60).expect("valid value");
+        assert_eq!(value.saturating_add(10).raw(), 63);
+        assert_eq!(value.saturating_add(255).raw(), 63);
+        assert_eq!(value.saturating_sub(61).raw(), 0);
+        let mut value = PropertyValue::default();
+        value.decrease();
+        assert_eq!(value.raw(), 0);
+
+        assert_eq!(
+            serde_json::from_str::<PropertyValue>(r#"{"raw": 12}"#).ok(),
+            PropertyValue::new(12)
+        );
+        assert!(serde_json::from_str::<PropertyValue>(r#"{"raw": 64}"#).is_err());
+    }
+
+    #[test]
+    fn corrupted_values_fail_to_encode() {
+        let mut dna = CreatureDNA::default();
+        let mut decoded = DecodedGeneInfo::default();
+        decoded.output_tags.push(OutputTag {
+            tag: PropertyValue::default(),
+            weight: PropertyValue { raw: 64 },
+        });
+        assert_eq!(
+            decoded.encode(),
+            Err("invalid output weight value 64".to_string())
+        );
+        dna.cells.push(NeuronProperties {
+            index: GridIndex2 { x: 2, y: 3 },
+            decoded,
+        });
+        assert_eq!(
+            dna.to_text(),
+            Err("cell [2][3]: invalid output weight value 64".to_string())
+        );
+    }
+
     #[test]
     fn mirror_map_wraps_without_panic() {
         assert_eq!(PropertyValue { raw: 14 }.as_mirror(), "X+Y+XY");
         assert_eq!(PropertyValue { raw: 15 }.as_mirror(), "P");
     }
+
+    mod codec {
+        use super::super::*;
+        use proptest::prelude::*;
+
+        fn property_value() -> impl Strategy<Value = PropertyValue> {
+            (0..64u8).prop_map(|raw| PropertyValue { raw })
+        }
+
+        fn gene() -> impl Strategy<Value = DecodedGeneInfo> {
+            (
+                property_value(),
+                property_value(),
+                prop::array::uniform8(property_value()),
+                property_value(),
+                prop::option::of(property_value()),
+                prop::option::of(property_value()),
+                prop::collection::vec((property_value(), property_value()), 0..24),
+            )
+                .prop_map(
+                    |(neuron_type, tag, properties, bias, ampersand, mirroring, outputs)| {
+                        DecodedGeneInfo {
+                            neuron_type: NeuronType(neuron_type),
+                            tag: GeneTag(tag),
+                            properties: properties.map(GeneProperty),
+                            bias: GeneBias(bias),
+                            ampersand: ampersand.map(GeneProperty),
+                            mirroring: mirroring.map(GeneMirroring),
+                            output_tags: outputs
+                                .into_iter()
+                                .map(|(tag, weight)| OutputTag { tag, weight })
+                                .collect(),
+                        }
+                    },
+                )
+        }
+
+        fn index() -> impl Strategy<Value = GridIndex2> {
+            (any::<u16>(), any::<u16>()).prop_map(|(x, y)| GridIndex2 { x, y })
+        }
+
+        /// Text the parser reads back unchanged: one line without surrounding whitespace.
+        fn word() -> impl Strategy<Value = String> {
+            "[A-Za-z0-9._-]{1,9}( [A-Za-z0-9._-]{1,9})?"
+        }
+
+        /// Blocks as the parser builds them: always named by a `//dna:` line, and with one
+        /// non-empty layer per z-level.
+        fn dna_block() -> impl Strategy<Value = DnaData> {
+            (
+                "[A-Za-z0-9._-]{0,9}",
+                prop::option::of((index(), word())),
+                prop::option::of(index()),
+                prop::option::of((index(), word())),
+                prop::collection::btree_map(
+                    any::<u16>(),
+                    prop::collection::vec((index(), gene()), 1..4),
+                    0..3,
+                ),
+            )
+                .prop_map(|(comment_name, name, location, creator, layers)| DnaData {
+                    dna_comment_name: Some(comment_name),
+                    dna_name: name.map(|(index, name)| DnaNameRecord { index, name }),
+                    dna_location: location,
+                    dna_creator: creator
+                        .map(|(index, creator)| DnaCreatorRecord { index, creator }),
+                    genes: DnaGenes(
+                        layers
+                            .into_iter()
+                            .map(|(z_level, genes)| DnaLayer {
+                                z_level,
+                                genes: genes
+                                    .into_iter()
+                                    .map(|(index, decoded)| GeneRecord { index, decoded })
+                                    .collect(),
+                            })
+                            .collect(),
+                    ),
+                })
+        }
+
+        /// Creatures without comments, which the parser skips.
+        fn creature() -> impl Strategy<Value = CreatureDNA> {
+            (
+                prop::option::of(word()),
+                prop::option::of(word()),
+                prop::option::of(word()),
+                prop::option::of(word()),
+                prop::collection::vec((index(), gene()), 0..6),
+                prop::collection::vec(dna_block(), 0..3),
+            )
+                .prop_map(|(name, date, version, skin_color, cells, dna)| {
+                    CreatureDNA {
+                        metadata: DnaMetadata {
+                            name,
+                            date,
+                            version,
+                        },
+                        creature: CreatureData { skin_color },
+                        cells: Cells(
+                            cells
+                                .into_iter()
+                                .map(|(index, decoded)| NeuronProperties { index, decoded })
+                                .collect(),
+                        ),
+                        dna,
+                        comments: Vec::new(),
+                    }
+                })
+        }
+
+        /// Lines of a creature file, some of them broken, mixed with random text.
+        fn creature_text() -> impl Strategy<Value = String> {
+            let line = prop_oneof![
+                1 => "\\PC{0,30}",
+                1 => "(//name:|//date:|//version:|//dna:|skin_color = |dna_location = )\
+                      [ \\[\\]0-9A-Za-z]{0,12}",
+                1 => "(neuron_properties|gene|dna_name|dna_creator)(\\[[0-9]{1,6}\\]){1,3} = \
+                      [*$#@%^+|{}~&_A-Za-z0-9?!\\[ ]{0,30}",
+                2 => (index(), gene()).prop_map(|(index, gene)| {
+                    let encoded = gene.encode().expect("valid gene");
+                    format!("neuron_properties[{}][{}] = {encoded}", index.x, index.y)
+                }),
+                2 => (index(), any::<u16>(), gene()).prop_map(|(index, z, gene)| {
+                    let encoded = gene.encode().expect("valid gene");
+                    format!("gene[{}][{}][{z}] = {encoded}", index.x, index.y)
+                }),
+            ];
+            prop::collection::vec(line, 0..12).prop_map(|lines| lines.join("\n"))
+        }
+
+        proptest! {
+            #[test]
+            fn genes_round_trip(gene in gene()) {
+                prop_assert_eq!(gene.encode().and_then(|text| DecodedGeneInfo::decode(&text)), Ok(gene));
+            }
+
+            #[test]
+            fn creatures_round_trip(dna in creature()) {
+                prop_assert_eq!(dna.to_text().and_then(|text| CreatureDNA::parse(&text)), Ok(dna));
+            }
+
+            #[test]
+            fn decoding_any_text_does_not_panic(
+                text in "\\PC*|[*$#@%^+|{}~&_A-Za-z0-9?!\\[]{0,40}"
+            ) {
+                if let Ok(gene) = DecodedGeneInfo::decode(&text) {
+                    prop_assert_eq!(gene.encode().and_then(|text| DecodedGeneInfo::decode(&text)), Ok(gene));
+                }
+            }
+
+            /// Whatever parses writes back into text that parses again. Files written by hand
+            /// may lose unnamed `//dna:` lines or comments on the first pass, so the model
+            /// is compared from the second one on.
+            #[test]
+            fn parsing_any_text_does_not_panic(text in creature_text()) {
+                if let Ok(dna) = CreatureDNA::parse(&text) {
+                    let written = dna.to_text().and_then(|text| CreatureDNA::parse(&text));
+                    prop_assert!(written.is_ok(), "{:?}", written);
+                    let written = written.unwrap();
+                    prop_assert_eq!(
+                        written.to_text().and_then(|text| CreatureDNA::parse(&text)),
+                        Ok(written)
+                    );
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,262 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+use crate::diff::{self, Change};
+use crate::dna_widget::DnaWidget;
+use crate::dnaparser::CreatureDNA;
+use crate::read_creature;
+
+const MAX_UNDO_STEPS: usize = 200;
+
+/// Identifies an open document. Unlike its tab index it stays the same while other tabs are
+/// opened or closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DocumentId(u64);
+
+/// A creature open in a tab, with its own selection, undo history and dirty state.
+pub struct Document {
+    id: DocumentId,
+    pub path: Option<PathBuf>,
+    pub dna: CreatureDNA,
+    pub dna_widget: DnaWidget,
+    /// Set when the file was changed by someone else, e.g. re-exported by the game.
+    pub external_change: Option<ExternalChange>,
+    history: History,
+    saved: CreatureDNA,
+    disk_modified: Option<SystemTime>,
+}
+
+/// A newer version of a document's file found on disk.
+pub struct ExternalChange {
+    pub dna: CreatureDNA,
+    /// Changes from the last loaded or saved version to `dna`.
+    pub changes: Vec<Change>,
+}
+
+/// Snapshot based undo history. Widgets edit `Document::dna` in place; `Document::commit`
+/// notices the difference to the last snapshot and records it as one undo step.
+struct History {
+    undo: Vec<CreatureDNA>,
+    redo: Vec<CreatureDNA>,
+    current: CreatureDNA,
+}
+
+impl Document {
+    pub fn new(dna: CreatureDNA, path: Option<PathBuf>) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        Self {
+            id: DocumentId(NEXT_ID.fetch_add(1, Ordering::Relaxed)),
+            disk_modified: path.as_deref().and_then(modified_time),
+            external_change: None,
+            path,
+            history: History {
+                undo: Vec::new(),
+                redo: Vec::new(),
+                current: dna.clone(),
+            },
+            saved: dna.clone(),
+            dna,
+            dna_widget: DnaWidget::new(),
+        }
+    }
+
+    pub fn id(&self) -> DocumentId {
+        self.id
+    }
+
+    pub fn title(&self) -> String {
+        let name = self
+            .path
+            .as_deref()
+            .and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().into_owned())
+            .or_else(|| self.dna.metadata.name.clone())
+            .unwrap_or_else(|| "untitled".to_string());
+        if self.is_dirty() {
+            format!("{name} *")
+        } else {
+            name
+        }
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dna != self.saved
+    }
+
+    pub fn mark_saved(&mut self, path: &Path) {
+        self.path = Some(path.to_path_buf());
+        self.saved = self.dna.clone();
+        self.disk_modified = modified_time(path);
+        self.external_change = None;
+    }
+
+    /// The version last loaded from or saved to disk.
+    pub fn saved_dna(&self) -> &CreatureDNA {
+        &self.saved
+    }
+
+    /// Checks whether the file was modified on disk since it was last loaded or saved, and
+    /// records the new version in `external_change` if its content differs.
+    pub fn poll_disk(&mut self) {
+        let Some(path) = self.path.as_deref() else {
+            return;
+        };
+        let modified = modified_time(path);
+        if modified.is_none() || modified == self.disk_modified {
+            return;
+        }
+        // A file that is still being written fails to parse; its next write is picked up again.
+        let Ok(dna) = read_creature(path) else {
+            return;
+        };
+        self.disk_modified = modified;
+
+        if self
+            .external_change
+            .as_ref()
+            .is_some_and(|change| change.dna == dna)
+        {
+            return;
+        }
+        self.offer_disk_version(dna);
+    }
+
+    /// Records `dna`, read from the file, in `external_change` if it differs from the saved
+    /// version. Also used to ask again after a merge with it was not applied.
+    pub fn offer_disk_version(&mut self, dna: CreatureDNA) {
+        self.external_change = (dna != self.saved).then(|| ExternalChange {
+            changes: diff::diff(&self.saved, &dna),
+            dna,
+        });
+    }
+
+    /// Makes `dna` the saved version without touching the edited one, so that local edits show
+    /// up as unsaved changes relative to the file on disk.
+    pub fn accept_disk_version(&mut self, dna: CreatureDNA) {
+        self.saved = dna;
+        self.external_change = None;
+    }
+
+    /// Replaces the whole creature as a single undoable step.
+    pub fn replace_dna(&mut self, dna: CreatureDNA) {
+        self.commit();
+        self.dna = dna;
+        self.commit();
+        self.dna_widget.refresh_from_dna();
+    }
+
+    /// Records edits made since the last call as one undo step.
+    pub fn commit(&mut self) {
+        if self.dna == self.history.current {
+            return;
+        }
+        let previous = std::mem::replace(&mut self.history.current, self.dna.clone());
+        self.history.undo.push(previous);
+        if self.history.undo.len() > MAX_UNDO_STEPS {
+            self.history.undo.remove(0);
+        }
+        self.history.redo.clear();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.undo.is_empty() || self.dna != self.history.current
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.history.redo.is_empty()
+    }
+
+    pub fn undo(&mut self) -> bool {
+        self.commit();
+        let Some(previous) = self.history.undo.pop() else {
+            return false;
+        };
+        let current = std::mem::replace(&mut self.history.current, previous.clone());
+        self.history.redo.push(current);
+        self.dna = previous;
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        self.commit();
+        let Some(next) = self.history.redo.pop() else {
+            return false;
+        };
+        let current = std::mem::replace(&mut self.history.current, next.clone());
+        self.history.undo.push(current);
+        self.dna = next;
+        true
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_redo_and_dirty_state() {
+        let input = std::fs::read_to_string("data/e5.txt").expect("failed to read data/e5.txt");
+        let original = crate::dnaparser::parse_creature_dna(&input).expect("failed to parse e5");
+        let mut doc = Document::new(original.clone(), Some(PathBuf::from("data/e5.txt")));
+        assert!(!doc.is_dirty());
+        assert!(!doc.can_undo());
+
+        doc.dna.cells.remove(0);
+        doc.commit();
+        let edited = doc.dna.clone();
+        doc.dna.metadata.name = Some("changed".to_string());
+        assert!(doc.is_dirty());
+
+        assert!(doc.undo());
+        assert_eq!(doc.dna, edited);
+        assert!(doc.undo());
+        assert_eq!(doc.dna, original);
+        assert!(!doc.is_dirty());
+        assert!(!doc.undo());
+
+        assert!(doc.redo());
+        assert_eq!(doc.dna, edited);
+        doc.dna.cells.remove(0);
+        doc.commit();
+        assert!(!doc.can_redo());
+    }
+
+    #[test]
+    fn poll_disk_detects_external_change() {
+        let input = std::fs::read_to_string("data/latch_mod.txt").expect("failed to read data");
+        let original = crate::dnaparser::parse_creature_dna(&input).expect("failed to parse");
+        let path = std::env::temp_dir().join(format!(
+            "external_change_{}_{}.txt",
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .expect("system clock before epoch")
+                .as_nanos()
+        ));
+        std::fs::write(&path, original.to_text().expect("encodable"))
+            .expect("failed to write temp file");
+        let mut doc = Document::new(original.clone(), Some(path.clone()));
+        doc.disk_modified = None;
+        doc.poll_disk();
+        assert!(doc.external_change.is_none());
+
+        let mut external = original.clone();
+        external.cells.remove(0);
+        std::fs::write(&path, external.to_text().expect("encodable"))
+            .expect("failed to write temp file");
+        doc.disk_modified = None;
+        doc.poll_disk();
+        let _ = std::fs::remove_file(&path);
+
+        let change = doc.external_change.as_ref().expect("change not detected");
+        assert_eq!(change.dna, external);
+        assert_eq!(change.changes.len(), 1);
+    }
+}
@@ -0,0 +1,512 @@
+//! Human readable JSON, RON and YAML representation of a creature.
+//!
+//! The game format stores every value as one letter of a 64 letter alphabet. This format
+//! instead uses neuron names, integers for properties and decoded decimals for thresholds and
+//! weights. Decimals snap to the nearest of the 64 steps on import, so exporting, importing and
+//! writing the game format again reproduces the original file.
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dnaparser::{
+    Cells, CreatureDNA, CreatureData, DecodedGeneInfo, DnaCreatorRecord, DnaData, DnaGenes,
+    DnaMetadata, DnaNameRecord, GeneBias, GeneMirroring, GeneProperty, GeneRecord, GeneTag,
+    GridIndex2, NeuronProperties, NeuronType, OutputTag, PropertyValue,
+};
+
+type Result<T> = std::result::Result<T, String>;
+
+/// Version written to `format_version`. Files with a newer version are rejected.
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// The game's own text format.
+    Text,
+    Json,
+    Ron,
+    Yaml,
+}
+
+impl Format {
+    pub const ALL: [Format; 4] = [Format::Text, Format::Json, Format::Ron, Format::Yaml];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Text => "text",
+            Format::Json => "json",
+            Format::Ron => "ron",
+            Format::Yaml => "yaml",
+        }
+    }
+
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Format::Text => &["txt"],
+            Format::Json => &["json"],
+            Format::Ron => &["ron"],
+            Format::Yaml => &["yaml", "yml"],
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.name() == name || format.extensions().contains(&name))
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|format| format.extensions().contains(&ext.as_str()))
+    }
+}
+
+pub fn export(dna: &CreatureDNA, format: Format) -> Result<String> {
+    let file = CreatureFile::from_creature(dna);
+    match format {
+        Format::Text => dna.to_text(),
+        Format::Json => serde_json::to_string_pretty(&file).map_err(|err| err.to_string()),
+        #[cfg(feature = "ron")]
+        Format::Ron => ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default())
+            .map_err(|err| err.to_string()),
+        #[cfg(feature = "yaml")]
+        Format::Yaml => serde_yaml::to_string(&file).map_err(|err| err.to_string()),
+        #[allow(unreachable_patterns)]
+        _ => Err(unsupported(format)),
+    }
+}
+
+pub fn import(content: &str, format: Format) -> Result<CreatureDNA> {
+    let file: CreatureFile = match format {
+        Format::Text => return CreatureDNA::parse(content),
+        Format::Json => serde_json::from_str(content).map_err(|err| err.to_string())?,
+        #[cfg(feature = "ron")]
+        Format::Ron => ron::from_str(content).map_err(|err| err.to_string())?,
+        #[cfg(feature = "yaml")]
+        Format::Yaml => serde_yaml::from_str(content).map_err(|err| err.to_string())?,
+        #[allow(unreachable_patterns)]
+        _ => return Err(unsupported(format)),
+    };
+    file.into_creature()
+}
+
+fn unsupported(format: Format) -> String {
+    format!(
+        "this build does not support {}, enable the '{}' feature",
+        format.name(),
+        format.name()
+    )
+}
+
+/// A whole creature file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CreatureFile {
+    /// Always `FORMAT_VERSION` when written by this editor.
+    pub format_version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    /// Game version the creature was exported with, e.g. "Gridworld v1.33".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Three letters for red, green and blue, as in the game format.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skin_color: Option<String>,
+    /// Neurons of the grown creature.
+    #[serde(default)]
+    pub cells: Vec<PlacedGene>,
+    #[serde(default)]
+    pub dna: Vec<Dna>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<String>,
+}
+
+/// A cell or gene together with its grid position.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlacedGene {
+    pub x: u16,
+    pub y: u16,
+    pub gene: Gene,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dna {
+    /// Name from the `//dna:` comment line.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<DnaRecord>,
+    /// Cell holding this DNA.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<Position>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creator: Option<DnaRecord>,
+    #[serde(default)]
+    pub layers: Vec<Layer>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Position {
+    pub x: u16,
+    pub y: u16,
+}
+
+/// A `dna_name` or `dna_creator` line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnaRecord {
+    pub x: u16,
+    pub y: u16,
+    pub value: String,
+}
+
+/// Genes of one z-level of a DNA.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Layer {
+    pub z: u16,
+    pub genes: Vec<PlacedGene>,
+}
+
+/// Decoded neuron properties, shared by cells and genes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Gene {
+    /// Neuron kind by name, e.g. "eye". Kinds without a unique name use their one letter code.
+    pub neuron: String,
+    /// Letter that output tags of other neurons use to address this neuron.
+    pub tag: char,
+    /// Properties 0 to 7 as integers 0..=63. Their meaning depends on the neuron kind.
+    pub properties: [u8; 8],
+    /// Firing threshold (the `~` bias), 0.0..=2.5.
+    pub threshold: f32,
+    /// The `&` field as an integer 0..=63, if present.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ampersand: Option<u8>,
+    /// Mirroring mode such as "P+X". Values without a named mode are written as integers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mirroring: Option<Mirroring>,
+    #[serde(default)]
+    pub outputs: Vec<Output>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Mirroring {
+    Mode(String),
+    Raw(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Output {
+    /// Tag letter of the receiving neurons.
+    pub target: char,
+    /// Connection weight, -2.5..=2.5.
+    pub weight: f32,
+}
+
+impl CreatureFile {
+    pub fn from_creature(dna: &CreatureDNA) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            name: dna.metadata.name.clone(),
+            date: dna.metadata.date.clone(),
+            version: dna.metadata.version.clone(),
+            skin_color: dna.creature.skin_color.clone(),
+            cells: dna
+                .cells
+                .iter()
+                .map(|cell| PlacedGene::new(cell.index, &cell.decoded))
+                .collect(),
+            dna: dna.dna.iter().map(Dna::from_dna).collect(),
+            comments: dna.comments.clone(),
+        }
+    }
+
+    pub fn into_creature(self) -> Result<CreatureDNA> {
+        if self.format_version > FORMAT_VERSION {
+            return Err(format!(
+                "format_version {} is newer than the supported version {}",
+                self.format_version, FORMAT_VERSION
+            ));
+        }
+
+        let cells = self
+            .cells
+            .into_iter()
+            .map(|cell| {
+                let index = GridIndex2 {
+                    x: cell.x,
+                    y: cell.y,
+                };
+                let decoded = cell
+                    .gene
+                    .into_decoded()
+                    .map_err(|err| format!("cell [{}][{}]: {err}", cell.x, cell.y))?;
+                Ok(NeuronProperties { index, decoded })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let dna = self
+            .dna
+            .into_iter()
+            .enumerate()
+            .map(|(idx, dna)| dna.into_dna().map_err(|err| format!("dna {idx}: {err}")))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(CreatureDNA {
+            metadata: DnaMetadata {
+                name: self.name,
+                date: self.date,
+                version: self.version,
+            },
+            creature: CreatureData {
+                skin_color: self.skin_color,
+            },
+            cells: Cells(cells),
+            dna,
+            comments: self.comments,
+        })
+    }
+}
+
+impl PlacedGene {
+    fn new(index: GridIndex2, decoded: &DecodedGeneInfo) -> Self {
+        Self {
+            x: index.x,
+            y: index.y,
+            gene: Gene::from_decoded(decoded),
+        }
+    }
+}
+
+impl Dna {
+    fn from_dna(dna: &DnaData) -> Self {
+        Self {
+            comment_name: dna.dna_comment_name.clone(),
+            name: dna.dna_name.as_ref().map(|record| DnaRecord {
+                x: record.index.x,
+                y: record.index.y,
+                value: record.name.clone(),
+            }),
+            location: dna.dna_location.map(|location| Position {
+                x: location.x,
+                y: location.y,
+            }),
+            creator: dna.dna_creator.as_ref().map(|record| DnaRecord {
+                x: record.index.x,
+                y: record.index.y,
+                value: record.creator.clone(),
+            }),
+            layers: dna
+                .genes
+                .iter()
+                .map(|layer| Layer {
+                    z: layer.z_level,
+                    genes: layer
+                        .genes
+                        .iter()
+                        .map(|gene| PlacedGene::new(gene.index, &gene.decoded))
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    fn into_dna(self) -> Result<DnaData> {
+        let mut genes = DnaGenes::default();
+        for layer in self.layers {
+            for gene in layer.genes {
+                let decoded = gene
+                    .gene
+                    .into_decoded()
+                    .map_err(|err| format!("gene [{}][{}][{}]: {err}", gene.x, gene.y, layer.z))?;
+                genes.push_gene(
+                    layer.z,
+                    GeneRecord {
+                        index: GridIndex2 {
+                            x: gene.x,
+                            y: gene.y,
+                        },
+                        decoded,
+                    },
+                );
+            }
+        }
+
+        Ok(DnaData {
+            dna_comment_name: self.comment_name,
+            dna_name: self.name.map(|record| DnaNameRecord {
+                index: GridIndex2 {
+                    x: record.x,
+                    y: record.y,
+                },
+                name: record.value,
+            }),
+            dna_location: self.location.map(|location| GridIndex2 {
+                x: location.x,
+                y: location.y,
+            }),
+            dna_creator: self.creator.map(|record| DnaCreatorRecord {
+                index: GridIndex2 {
+                    x: record.x,
+                    y: record.y,
+                },
+                creator: record.value,
+            }),
+            genes,
+        })
+    }
+}
+
+impl Gene {
+    pub fn from_decoded(info: &DecodedGeneInfo) -> Self {
+        let neuron = if info.neuron_type.has_unique_name() {
+            info.neuron_type.to_name().to_string()
+        } else {
+            info.neuron_type.to_char()
+        };
+        let mirroring = info.mirroring.map(|GeneMirroring(mirror)| {
+            if PropertyValue::from_mirror(mirror.as_mirror()) == Some(mirror) {
+                Mirroring::Mode(mirror.as_mirror().to_string())
+            } else {
+                Mirroring::Raw(mirror.raw())
+            }
+        });
+
+        Self {
+            neuron,
+            tag: info.tag.0.to_char().unwrap_or('?'),
+            properties: info.properties.map(|prop| prop.0.as_int()),
+            threshold: info.bias.0.as_bias(),
+            ampersand: info.ampersand.map(|ampersand| ampersand.0.as_int()),
+            mirroring,
+            outputs: info
+                .output_tags
+                .iter()
+                .map(|output| Output {
+                    target: output.tag.to_char().unwrap_or('?'),
+                    weight: output.weight.as_weight(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn into_decoded(self) -> Result<DecodedGeneInfo> {
+        let neuron_type = NeuronType::from_name(&self.neuron)
+            .or_else(|| {
+                let mut chars = self.neuron.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => PropertyValue::from_char(c).map(NeuronType),
+                    _ => None,
+                }
+            })
+            .ok_or_else(|| format!("unknown neuron '{}'", self.neuron))?;
+        let tag = letter(self.tag, "tag")?;
+
+        let mut properties = [GeneProperty::default(); 8];
+        for (idx, (prop, value)) in properties.iter_mut().zip(self.properties).enumerate() {
+            *prop = GeneProperty(integer(value, &format!("property {idx}"))?);
+        }
+
+        let bias = PropertyValue::from_threshold(self.threshold)
+            .ok_or_else(|| format!("threshold {} out of range 0..2.5", self.threshold))?;
+        let ampersand = self
+            .ampersand
+            .map(|value| integer(value, "ampersand").map(GeneProperty))
+            .transpose()?;
+        let mirroring = match self.mirroring {
+            None => None,
+            Some(Mirroring::Mode(mode)) => Some(
+                PropertyValue::from_mirror(&mode)
+                    .ok_or_else(|| format!("unknown mirroring mode '{mode}'"))?,
+            ),
+            Some(Mirroring::Raw(value)) => Some(integer(value, "mirroring")?),
+        };
+        let output_tags = self
+            .outputs
+            .into_iter()
+            .map(|output| {
+                let weight = PropertyValue::from_weight(output.weight)
+                    .ok_or_else(|| format!("weight {} out of range -2.5..2.5", output.weight))?;
+                Ok(OutputTag {
+                    tag: letter(output.target, "output target")?,
+                    weight,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(DecodedGeneInfo {
+            neuron_type,
+            tag: GeneTag(tag),
+            properties,
+            bias: GeneBias(bias),
+            ampersand,
+            mirroring: mirroring.map(GeneMirroring),
+            output_tags,
+        })
+    }
+}
+
+fn letter(c: char, what: &str) -> Result<PropertyValue> {
+    PropertyValue::from_char(c).ok_or_else(|| format!("invalid {what} '{c}'"))
+}
+
+fn integer(value: u8, what: &str) -> Result<PropertyValue> {
+    PropertyValue::from_int(value).ok_or_else(|| format!("{what} {value} out of range 0..63"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data_files() -> Vec<(String, CreatureDNA)> {
+        crate::library::creature_files(Path::new("data"))
+            .expect("failed to list data")
+            .into_iter()
+            .filter_map(|path| {
+                let dna = crate::read_creature(&path).ok()?;
+                Some((path.display().to_string(), dna))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_format_round_trips_to_game_text() {
+        let files = data_files();
+        assert!(!files.is_empty());
+        for (path, dna) in files {
+            for format in Format::ALL {
+                let exported = export(&dna, format)
+                    .unwrap_or_else(|err| panic!("{path} {}: {err}", format.name()));
+                let imported = import(&exported, format)
+                    .unwrap_or_else(|err| panic!("{path} {}: {err}", format.name()));
+                assert_eq!(
+                    imported.to_text(),
+                    dna.to_text(),
+                    "{path} {}",
+                    format.name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn json_uses_decoded_values() {
+        let input = "*R$m#7@0%a^9+3|M{U}M~m&W_B[vm[gW";
+        let (_, decoded) = crate::dnaparser::parser::decode_gene_info(input).unwrap();
+        let gene = Gene::from_decoded(&decoded);
+        assert_eq!(gene.neuron, "eye");
+        assert_eq!(gene.tag, 'm');
+        assert_eq!(gene.properties[0], 59);
+        assert_eq!(gene.mirroring, Some(Mirroring::Mode("P+X".to_string())));
+        assert_eq!(gene.outputs[0].target, 'v');
+        assert!((gene.threshold - decoded.bias.0.as_bias()).abs() < f32::EPSILON);
+
+        let mut edited = gene.clone();
+        edited.outputs[0].weight = 9.0;
+        assert!(edited.into_decoded().is_err());
+        let mut edited = gene;
+        edited.neuron = "no such neuron".to_string();
+        assert!(edited.into_decoded().is_err());
+    }
+}
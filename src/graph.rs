@@ -0,0 +1,291 @@
+//! The output tag network as Graphviz DOT or Mermaid text, for design reviews. Cells connect to
+//! cells and genes to the genes of the same layer, like in the game.
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::dnaparser::{CreatureDNA, DecodedGeneInfo, GridIndex2};
+use crate::pdf_infos::{NeuronCategory, neuron_category};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+impl GraphFormat {
+    pub const ALL: [GraphFormat; 2] = [GraphFormat::Dot, GraphFormat::Mermaid];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GraphFormat::Dot => "Graphviz DOT",
+            GraphFormat::Mermaid => "Mermaid",
+        }
+    }
+
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            GraphFormat::Dot => &["dot", "gv"],
+            GraphFormat::Mermaid => &["mmd", "mermaid"],
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "dot" | "gv" | "graphviz" => Some(GraphFormat::Dot),
+            "mermaid" | "mmd" => Some(GraphFormat::Mermaid),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?;
+        Self::ALL
+            .into_iter()
+            .find(|format| format.extensions().contains(&extension))
+    }
+}
+
+/// How nodes are grouped into subgraphs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Clustering {
+    #[default]
+    Category,
+    /// The cells, and each layer of each DNA block.
+    Layer,
+}
+
+impl Clustering {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "category" => Some(Clustering::Category),
+            "layer" => Some(Clustering::Layer),
+            _ => None,
+        }
+    }
+}
+
+struct Node {
+    id: String,
+    label: String,
+    cluster: String,
+}
+
+struct Edge {
+    from: String,
+    to: String,
+    weight: f32,
+}
+
+/// The nodes and edges of one group of records that address each other: the cells, or the
+/// genes of one layer.
+fn add_scope<'a>(
+    nodes: &mut Vec<Node>,
+    edges: &mut Vec<Edge>,
+    records: impl Iterator<Item = (GridIndex2, &'a DecodedGeneInfo)> + Clone,
+    id_prefix: &str,
+    layer_name: &str,
+    clustering: Clustering,
+) {
+    let id = |index: GridIndex2| format!("{id_prefix}_{}_{}", index.x, index.y);
+    for (index, info) in records.clone() {
+        let category = neuron_category(info.neuron_type.0.to_char().unwrap_or('?'));
+        nodes.push(Node {
+            id: id(index),
+            label: format!(
+                "{}\ntag {}\n[{}][{}]",
+                info.neuron_type.to_name(),
+                info.tag.to_char(),
+                index.x,
+                index.y
+            ),
+            cluster: match clustering {
+                Clustering::Category => category.name().to_string(),
+                Clustering::Layer => layer_name.to_string(),
+            },
+        });
+        for output in &info.output_tags {
+            for (target, _) in records
+                .clone()
+                .filter(|(_, target)| target.tag.0 == output.tag)
+            {
+                edges.push(Edge {
+                    from: id(index),
+                    to: id(target),
+                    weight: output.weight.as_weight(),
+                });
+            }
+        }
+    }
+}
+
+fn network(dna: &CreatureDNA, clustering: Clustering) -> (Vec<Node>, Vec<Edge>) {
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    add_scope(
+        &mut nodes,
+        &mut edges,
+        dna.cells.iter().map(|cell| (cell.index, &cell.decoded)),
+        "cell",
+        "cells",
+        clustering,
+    );
+    for (dna_idx, block) in dna.dna.iter().enumerate() {
+        for layer in block.genes.iter() {
+            add_scope(
+                &mut nodes,
+                &mut edges,
+                layer.genes.iter().map(|gene| (gene.index, &gene.decoded)),
+                &format!("gene{dna_idx}_{}", layer.z_level),
+                &format!("dna {dna_idx} layer {}", layer.z_level),
+                clustering,
+            );
+        }
+    }
+    if clustering == Clustering::Category {
+        let order = |cluster: &str| {
+            NeuronCategory::ALL
+                .iter()
+                .position(|category| category.name() == cluster)
+        };
+        nodes.sort_by_key(|node| order(&node.cluster));
+    }
+    (nodes, edges)
+}
+
+/// Nodes grouped by cluster, in order of first appearance.
+fn clusters(nodes: &[Node]) -> Vec<(&str, Vec<&Node>)> {
+    let mut clusters: Vec<(&str, Vec<&Node>)> = Vec::new();
+    for node in nodes {
+        match clusters.iter_mut().find(|(name, _)| *name == node.cluster) {
+            Some((_, members)) => members.push(node),
+            None => clusters.push((&node.cluster, vec![node])),
+        }
+    }
+    clusters
+}
+
+pub fn export(dna: &CreatureDNA, format: GraphFormat, clustering: Clustering) -> String {
+    let name = dna.metadata.name.as_deref().unwrap_or("creature");
+    let (nodes, edges) = network(dna, clustering);
+    match format {
+        GraphFormat::Dot => to_dot(name, &nodes, &edges),
+        GraphFormat::Mermaid => to_mermaid(name, &nodes, &edges),
+    }
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn to_dot(name: &str, nodes: &[Node], edges: &[Edge]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "digraph \"{}\" {{", dot_escape(name));
+    let _ = writeln!(out, "  rankdir=LR;");
+    let _ = writeln!(out, "  node [shape=box, fontname=\"monospace\"];");
+    for (idx, (cluster, members)) in clusters(nodes).into_iter().enumerate() {
+        let _ = writeln!(out, "  subgraph cluster_{idx} {{");
+        let _ = writeln!(out, "    label=\"{}\";", dot_escape(cluster));
+        for node in members {
+            let _ = writeln!(
+                out,
+                "    {} [label=\"{}\"];",
+                node.id,
+                dot_escape(&node.label)
+            );
+        }
+        let _ = writeln!(out, "  }}");
+    }
+    for edge in edges {
+        let color = if edge.weight < 0.0 {
+            "red"
+        } else {
+            "darkgreen"
+        };
+        let _ = writeln!(
+            out,
+            "  {} -> {} [label=\"{:.2}\", color={color}];",
+            edge.from, edge.to, edge.weight
+        );
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;").replace('\n', "<br/>")
+}
+
+fn to_mermaid(name: &str, nodes: &[Node], edges: &[Edge]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "---\ntitle: \"{}\"\n---", mermaid_escape(name));
+    let _ = writeln!(out, "flowchart LR");
+    for (idx, (cluster, members)) in clusters(nodes).into_iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "  subgraph cluster_{idx} [\"{}\"]",
+            mermaid_escape(cluster)
+        );
+        for node in members {
+            let _ = writeln!(out, "    {}[\"{}\"]", node.id, mermaid_escape(&node.label));
+        }
+        let _ = writeln!(out, "  end");
+    }
+    for edge in edges {
+        let _ = writeln!(
+            out,
+            "  {} -- \"{:.2}\" --> {}",
+            edge.from, edge.weight, edge.to
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exports_nodes_edges_and_clusters() {
+        let dna = crate::read_creature("data/e5.txt").expect("failed to read data/e5.txt");
+        let (nodes, edges) = network(&dna, Clustering::Category);
+        let genes: usize = dna.dna.iter().map(|block| block.genes.gene_count()).sum();
+        assert_eq!(nodes.len(), dna.cells.len() + genes);
+        assert!(!edges.is_empty());
+
+        let cell = &dna.cells[0];
+        let id = format!("cell_{}_{}", cell.index.x, cell.index.y);
+        let dot = export(&dna, GraphFormat::Dot, Clustering::Category);
+        assert!(dot.starts_with("digraph \"e5\" {"));
+        assert!(dot.contains(&format!(
+            "{id} [label=\"{}\\ntag {}\\n[{}][{}]\"];",
+            cell.decoded.neuron_type.to_name(),
+            cell.decoded.tag.to_char(),
+            cell.index.x,
+            cell.index.y
+        )));
+        assert!(dot.contains("label=\"actuators\";"));
+        assert_eq!(dot.matches(" -> ").count(), edges.len());
+
+        let mermaid = export(&dna, GraphFormat::Mermaid, Clustering::Layer);
+        assert!(mermaid.contains("subgraph cluster_0 [\"cells\"]"));
+        assert!(mermaid.contains("[\"dna 0 layer 0\"]"));
+        assert_eq!(mermaid.matches(" --> ").count(), edges.len());
+        assert_eq!(
+            mermaid.matches("subgraph").count(),
+            mermaid.lines().filter(|line| line.trim() == "end").count()
+        );
+    }
+
+    #[test]
+    fn formats_from_names_and_paths() {
+        assert_eq!(GraphFormat::from_name("DOT"), Some(GraphFormat::Dot));
+        assert_eq!(
+            GraphFormat::from_path(Path::new("wiring.mmd")),
+            Some(GraphFormat::Mermaid)
+        );
+        assert_eq!(GraphFormat::from_path(Path::new("wiring.txt")), None);
+        assert_eq!(Clustering::from_name("layer"), Some(Clustering::Layer));
+    }
+}
@@ -0,0 +1,195 @@
+//! Scanning of a folder of exported creatures, e.g. the game's `Creatures/Exported/DNA` folder.
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+use crate::dnaparser::CreatureDNA;
+use crate::read_creature;
+use crate::render::{self, RenderOptions, Scene};
+
+pub struct LibraryEntry {
+    pub path: PathBuf,
+    pub dna: Result<CreatureDNA, String>,
+    /// Thumbnail, built once while scanning.
+    pub scene: Option<Scene>,
+}
+
+impl LibraryEntry {
+    pub fn load(path: PathBuf) -> Self {
+        let dna = read_creature(&path);
+        let scene = dna
+            .as_ref()
+            .ok()
+            .map(|dna| render::scene(dna, &RenderOptions::default()));
+        Self { path, dna, scene }
+    }
+
+    /// Creature name from the file header, falling back to the file name.
+    pub fn name(&self) -> String {
+        self.dna
+            .as_ref()
+            .ok()
+            .and_then(|dna| dna.metadata.name.clone())
+            .or_else(|| {
+                self.path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn date(&self) -> Option<&str> {
+        self.dna.as_ref().ok()?.metadata.date.as_deref()
+    }
+
+    pub fn version(&self) -> Option<&str> {
+        self.dna.as_ref().ok()?.metadata.version.as_deref()
+    }
+
+    pub fn cell_count(&self) -> usize {
+        self.dna.as_ref().map(|dna| dna.cells.len()).unwrap_or(0)
+    }
+
+    pub fn gene_count(&self) -> usize {
+        self.dna
+            .as_ref()
+            .map(|dna| dna.dna.iter().map(|block| block.genes.gene_count()).sum())
+            .unwrap_or(0)
+    }
+
+    pub fn matches(&self, filter: &str) -> bool {
+        if filter.is_empty() {
+            return true;
+        }
+        let filter = filter.to_lowercase();
+        self.name().to_lowercase().contains(&filter)
+            || self
+                .path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().to_lowercase().contains(&filter))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortKey {
+    #[default]
+    Name,
+    Date,
+    Version,
+    Cells,
+    Genes,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 5] = [
+        SortKey::Name,
+        SortKey::Date,
+        SortKey::Version,
+        SortKey::Cells,
+        SortKey::Genes,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SortKey::Name => "name",
+            SortKey::Date => "date",
+            SortKey::Version => "version",
+            SortKey::Cells => "cells",
+            SortKey::Genes => "genes",
+        }
+    }
+
+    pub fn compare(self, a: &LibraryEntry, b: &LibraryEntry) -> Ordering {
+        let ordering = match self {
+            SortKey::Name => a.name().to_lowercase().cmp(&b.name().to_lowercase()),
+            SortKey::Date => a.date().cmp(&b.date()),
+            SortKey::Version => a.version().cmp(&b.version()),
+            SortKey::Cells => a.cell_count().cmp(&b.cell_count()),
+            SortKey::Genes => a.gene_count().cmp(&b.gene_count()),
+        };
+        ordering.then_with(|| a.path.cmp(&b.path))
+    }
+}
+
+/// Creature files directly inside `folder`, sorted by path.
+pub fn creature_files(folder: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = std::fs::read_dir(folder)
+        .map_err(|err| format!("failed to read '{}': {err}", folder.display()))?;
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("txt"))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Background parse of every creature file in a folder.
+pub struct LibraryScan {
+    pub total: usize,
+    receiver: Receiver<LibraryEntry>,
+}
+
+impl LibraryScan {
+    /// Starts parsing the files of `folder` on a worker thread. `notify` is called after each
+    /// parsed file, e.g. to request a repaint.
+    pub fn start<N>(folder: &Path, notify: N) -> Result<Self, String>
+    where
+        N: Fn() + Send + 'static,
+    {
+        let files = creature_files(folder)?;
+        let total = files.len();
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            for path in files {
+                if sender.send(LibraryEntry::load(path)).is_err() {
+                    return;
+                }
+                notify();
+            }
+        });
+        Ok(Self { total, receiver })
+    }
+
+    /// Moves finished entries into `out`. Returns true once all files have been parsed.
+    pub fn poll(&self, out: &mut Vec<LibraryEntry>) -> bool {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(entry) => out.push(entry),
+                Err(TryRecvError::Empty) => return false,
+                Err(TryRecvError::Disconnected) => return true,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_parses_data_folder() {
+        let scan = LibraryScan::start(Path::new("data"), || {}).expect("failed to scan data");
+        let mut entries = Vec::new();
+        while !scan.poll(&mut entries) {
+            std::thread::yield_now();
+        }
+        assert_eq!(entries.len(), scan.total);
+
+        let e5 = entries
+            .iter()
+            .find(|entry| entry.path.ends_with("e5.txt"))
+            .expect("e5.txt not scanned");
+        assert_eq!(e5.name(), "e5");
+        assert_eq!(e5.version(), Some("Gridworld v1.33"));
+        assert!(e5.cell_count() > 0);
+        assert!(e5.gene_count() > 0);
+        assert!(e5.scene.is_some());
+        assert!(e5.matches("E5"));
+        assert!(!e5.matches("latch"));
+    }
+}
@@ -0,0 +1,190 @@
+use std::path::{Path, PathBuf};
+
+use egui::{Color32, RichText, ScrollArea, Sense, Ui, Vec2};
+
+use crate::library::{LibraryEntry, LibraryScan, SortKey};
+use crate::render::{self, Scene};
+
+const THUMBNAIL_SIZE: f32 = 48.0;
+
+/// Side panel listing the creatures of a folder.
+#[derive(Default)]
+pub struct LibraryWidget {
+    folder: Option<PathBuf>,
+    entries: Vec<LibraryEntry>,
+    scan: Option<LibraryScan>,
+    error: Option<String>,
+    filter: String,
+    sort_key: SortKey,
+    descending: bool,
+    /// Order `entries` were last sorted in, `None` if entries arrived since.
+    sorted_by: Option<(SortKey, bool)>,
+}
+
+impl LibraryWidget {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn folder(&self) -> Option<&Path> {
+        self.folder.as_deref()
+    }
+
+    pub fn scan(&mut self, ctx: &egui::Context, folder: &Path) {
+        self.folder = Some(folder.to_path_buf());
+        self.entries.clear();
+        self.sorted_by = None;
+        let ctx = ctx.clone();
+        match LibraryScan::start(folder, move || ctx.request_repaint()) {
+            Ok(scan) => {
+                self.scan = Some(scan);
+                self.error = None;
+            }
+            Err(err) => {
+                self.scan = None;
+                self.error = Some(err);
+            }
+        }
+    }
+
+    /// Returns the path of a creature the user wants to open.
+    pub fn ui(&mut self, ui: &mut Ui) -> Option<PathBuf> {
+        let count = self.entries.len();
+        if let Some(scan) = self.scan.as_ref()
+            && scan.poll(&mut self.entries)
+        {
+            self.scan = None;
+        }
+        if self.entries.len() != count {
+            self.sorted_by = None;
+        }
+
+        let mut open = None;
+        ui.horizontal(|ui| {
+            if ui.button("Folder...").clicked() {
+                let mut dialog = rfd::FileDialog::new();
+                if let Some(folder) = self.folder.as_ref() {
+                    dialog = dialog.set_directory(folder);
+                }
+                if let Some(folder) = dialog.pick_folder() {
+                    self.scan(ui.ctx(), &folder);
+                }
+            }
+            if let Some(folder) = self.folder.clone()
+                && ui.button("Rescan").clicked()
+            {
+                self.scan(ui.ctx(), &folder);
+            }
+        });
+        match self.folder.as_ref() {
+            Some(folder) => ui.label(folder.display().to_string()),
+            None => ui.label("No library folder selected."),
+        };
+        if let Some(err) = self.error.as_deref() {
+            ui.colored_label(Color32::RED, err);
+        }
+        if let Some(scan) = self.scan.as_ref() {
+            ui.label(format!("Scanning {}/{}...", self.entries.len(), scan.total));
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Filter");
+            ui.text_edit_singleline(&mut self.filter);
+        });
+        ui.horizontal(|ui| {
+            ui.label("Sort");
+            egui::ComboBox::from_id_salt("library_sort")
+                .selected_text(self.sort_key.name())
+                .show_ui(ui, |ui| {
+                    for key in SortKey::ALL {
+                        ui.selectable_value(&mut self.sort_key, key, key.name());
+                    }
+                });
+            ui.checkbox(&mut self.descending, "descending");
+        });
+        ui.separator();
+
+        let sort_key = self.sort_key;
+        let descending = self.descending;
+        if self.sorted_by != Some((sort_key, descending)) {
+            self.entries.sort_by(|a, b| {
+                let ordering = sort_key.compare(a, b);
+                if descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+            self.sorted_by = Some((sort_key, descending));
+        }
+
+        let shown: Vec<&LibraryEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.matches(&self.filter))
+            .collect();
+        // Rows hold the name, two lines of details and the Open button next to the thumbnail.
+        let spacing = ui.spacing();
+        let row_height =
+            THUMBNAIL_SIZE.max(4.0 * spacing.interact_size.y + 3.0 * spacing.item_spacing.y);
+        ScrollArea::vertical().show_rows(ui, row_height, shown.len(), |ui, rows| {
+            for entry in &shown[rows] {
+                ui.push_id(&entry.path, |ui| {
+                    let size = Vec2::new(ui.available_width(), row_height);
+                    let clicked = ui
+                        .allocate_ui(size, |ui| {
+                            ui.set_min_size(size);
+                            entry_ui(ui, entry)
+                        })
+                        .inner;
+                    if clicked {
+                        open = Some(entry.path.clone());
+                    }
+                });
+            }
+        });
+        open
+    }
+}
+
+/// Returns true if the entry was double clicked or its Open button pressed.
+fn entry_ui(ui: &mut Ui, entry: &LibraryEntry) -> bool {
+    let mut open = false;
+    ui.horizontal(|ui| {
+        let thumbnail = thumbnail_ui(ui, entry.scene.as_ref());
+        open |= thumbnail.double_clicked();
+
+        ui.vertical(|ui| {
+            let name = ui
+                .add(egui::Label::new(RichText::new(entry.name()).strong()).sense(Sense::click()));
+            open |= name.double_clicked();
+            match entry.dna.as_ref() {
+                Ok(_) => {
+                    ui.label(format!(
+                        "{} | {}",
+                        entry.date().unwrap_or("-"),
+                        entry.version().unwrap_or("-")
+                    ));
+                    ui.label(format!(
+                        "{} cells, {} genes",
+                        entry.cell_count(),
+                        entry.gene_count()
+                    ));
+                }
+                Err(err) => {
+                    ui.colored_label(Color32::RED, err);
+                }
+            }
+            open |= ui.small_button("Open").clicked();
+        });
+    });
+    open
+}
+
+fn thumbnail_ui(ui: &mut Ui, scene: Option<&Scene>) -> egui::Response {
+    let (rect, response) = ui.allocate_exact_size(Vec2::splat(THUMBNAIL_SIZE), Sense::click());
+    if let Some(scene) = scene {
+        render::paint(&ui.painter_at(rect), rect, scene);
+    }
+    response
+}
@@ -1,9 +1,31 @@
+mod actions;
 mod app_state;
+mod cli;
+mod command_palette;
+mod diff;
 mod dna_widget;
 mod dnaparser;
+mod document;
+mod exchange;
+mod graph;
+mod library;
+mod library_widget;
+mod merge;
+mod merge_widget;
 mod pdf_infos;
+mod preferences;
+mod preferences_widget;
+mod render;
+mod search;
+mod stats;
+mod stats_widget;
+mod template;
+mod template_widget;
+mod transform;
+mod validate;
+mod workspace;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{
     Arc,
     atomic::{AtomicBool, Ordering},
@@ -11,15 +33,60 @@ use std::sync::{
 
 use eframe::egui;
 
-use crate::{app_state::AppState, dna_widget::DnaWidget, dnaparser::CreatureDNA};
+use crate::{
+    actions::Action,
+    app_state::AppState,
+    command_palette::CommandPalette,
+    dna_widget::DnaWidget,
+    dnaparser::CreatureDNA,
+    document::{Document, DocumentId},
+    exchange::Format,
+    graph::{Clustering, GraphFormat},
+    library_widget::LibraryWidget,
+    merge_widget::{MergeAction, MergeWidget},
+    preferences_widget::PreferencesWindow,
+    template_widget::TemplateWidget,
+    workspace::{PanelLayout, Workspace, WorkspaceFile},
+};
 
 const DATA_DIR: &str = "gridworld-editor";
+/// Where the game exports creatures to by default.
+const DEFAULT_LIBRARY_FOLDER: &str =
+    r"C:\Program Files (x86)\Steam\steamapps\common\Gridworld\app\data\Creatures\Exported\DNA";
+
+/// A state.json that exists but could not be loaded.
+struct StateLoadError {
+    message: String,
+    /// Whether the file was copied to state.json.bak, so that it may be overwritten on exit.
+    backed_up: bool,
+}
 
-fn on_start() -> AppState {
-    std::fs::read(format!("{DATA_DIR}/state.json"))
-        .ok()
-        .and_then(|bytes| serde_json::from_slice::<AppState>(&bytes).ok())
-        .unwrap_or_default()
+fn on_start() -> Result<AppState, StateLoadError> {
+    let state_path = Path::new(DATA_DIR).join("state.json");
+    let Ok(bytes) = std::fs::read(&state_path) else {
+        return Ok(AppState::default());
+    };
+    AppState::from_json(&bytes).map_err(|err| {
+        let backup_path = state_path.with_extension("json.bak");
+        match std::fs::copy(&state_path, &backup_path) {
+            Ok(_) => StateLoadError {
+                message: format!(
+                    "Could not load '{}': {err}. It was kept as '{}' and the defaults are used.",
+                    state_path.display(),
+                    backup_path.display()
+                ),
+                backed_up: true,
+            },
+            Err(copy_err) => StateLoadError {
+                message: format!(
+                    "Could not load '{}': {err}. It will not be overwritten on exit \
+                     (failed to back it up: {copy_err}).",
+                    state_path.display()
+                ),
+                backed_up: false,
+            },
+        }
+    })
 }
 
 fn on_exit(app_state: &AppState) {
@@ -34,66 +101,230 @@ fn on_exit(app_state: &AppState) {
     }
 }
 
+fn read_creature<P: AsRef<Path>>(filepath: P) -> Result<CreatureDNA, String> {
+    let path = filepath.as_ref();
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| format!("failed to read '{}': {err}", path.display()))?;
+    dnaparser::parse_creature_dna(&content)
+        .map_err(|err| format!("failed to parse '{}': {err}", path.display()))
+}
+
 fn load_creature<P: AsRef<Path>>(
     app_state: &mut AppState,
     filepath: P,
-    dna: &mut Option<CreatureDNA>,
-) -> Result<(), String> {
+) -> Result<Document, String> {
     let path = filepath.as_ref();
-    let content = std::fs::read_to_string(path)
-        .map_err(|err| format!("failed to read '{}': {err}", path.display()))?;
-    let decoded = dnaparser::parse_creature_dna(&content)?;
+    let decoded = read_creature(path)?;
 
-    app_state.open_file = Some(path.to_path_buf());
     app_state.last_folder = path.parent().map(Path::to_path_buf);
-    *dna = Some(decoded);
 
-    Ok(())
+    Ok(Document::new(decoded, Some(path.to_path_buf())))
 }
 
 fn save_creature<P: AsRef<Path>>(filepath: P, dna: &CreatureDNA) -> Result<(), String> {
     let path = filepath.as_ref();
-    let content = dna.to_text();
+    let content = encode_creature(path, dna)?;
+    write_creature(path, &content)
+}
+
+fn encode_creature(path: &Path, dna: &CreatureDNA) -> Result<String, String> {
+    dna.to_text()
+        .map_err(|err| format!("failed to encode '{}': {err}", path.display()))
+}
+
+fn write_creature(path: &Path, content: &str) -> Result<(), String> {
     std::fs::write(path, content)
         .map_err(|err| format!("failed to write '{}': {err}", path.display()))
 }
 
+/// Keeps up to `count` earlier versions of `path` before it is overwritten: the current file
+/// becomes `<path>.bak1` and older backups move one number up.
+fn rotate_backups(path: &Path, count: u8) -> Result<(), String> {
+    if count == 0 || !path.is_file() {
+        return Ok(());
+    }
+    let backup = |n: u8| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(format!(".bak{n}"));
+        PathBuf::from(name)
+    };
+    for n in (1..count).rev() {
+        let from = backup(n);
+        if from.is_file() {
+            std::fs::rename(&from, backup(n + 1))
+                .map_err(|err| format!("failed to rotate '{}': {err}", from.display()))?;
+        }
+    }
+    std::fs::copy(path, backup(1))
+        .map(|_| ())
+        .map_err(|err| format!("failed to back up '{}': {err}", path.display()))
+}
+
 #[test]
 fn test_load_creature() {
-    let mut dna = None;
     let mut app_state = AppState::default();
-    assert!(load_creature(&mut app_state, "data/e5.txt", &mut dna).is_ok())
+    assert!(load_creature(&mut app_state, "data/e5.txt").is_ok())
+}
+
+#[test]
+fn test_rotate_backups() {
+    let dir = std::env::temp_dir().join(format!("gridworld-backups-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("creature.txt");
+    for version in ["1", "2", "3", "4"] {
+        rotate_backups(&path, 2).unwrap();
+        std::fs::write(&path, version).unwrap();
+    }
+    let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+    assert_eq!(read("creature.txt"), "4");
+    assert_eq!(read("creature.txt.bak1"), "3");
+    assert_eq!(read("creature.txt.bak2"), "2");
+    assert!(!dir.join("creature.txt.bak3").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
 }
 
 struct GridworldApp {
     app_state: AppState,
-    creature_dna: Option<CreatureDNA>,
-    dna_widget: DnaWidget,
+    documents: Vec<Document>,
+    active_document: usize,
+    /// Tab waiting for the user to decide what happens to its unsaved changes.
+    pending_close: Option<DocumentId>,
+    merge_widget: Option<MergeWidget>,
+    library_widget: LibraryWidget,
+    template_widget: TemplateWidget,
+    command_palette: CommandPalette,
+    preferences_window: PreferencesWindow,
+    show_find: bool,
+    /// Current widths of the side panels, saved with workspaces.
+    panel_layout: PanelLayout,
+    /// Layout of a workspace that was just opened, applied in the next frame.
+    pending_layout: Option<PanelLayout>,
+    /// Name typed in the Workspaces menu.
+    workspace_name: String,
+    last_disk_check: f64,
     status_message: Option<String>,
+    /// Whether state.json is written on exit; off when an unreadable one could not be kept.
+    save_state: bool,
     shutdown_requested: Arc<AtomicBool>,
 }
 
 impl GridworldApp {
-    fn new(shutdown_requested: Arc<AtomicBool>) -> Self {
-        let mut app_state = on_start();
-        let mut creature_dna = None;
-        let mut status_message = None;
+    fn new(ctx: &egui::Context, shutdown_requested: Arc<AtomicBool>) -> Self {
+        let (mut app_state, state_error) = match on_start() {
+            Ok(app_state) => (app_state, None),
+            Err(err) => (AppState::default(), Some(err)),
+        };
+        let save_state = state_error.as_ref().is_none_or(|err| err.backed_up);
+        let mut documents = Vec::new();
+        let mut errors = Vec::new();
 
-        if let Some(last_open_file) = app_state.open_file.clone() {
-            if let Err(err) = load_creature(&mut app_state, &last_open_file, &mut creature_dna) {
-                status_message = Some(format!("Failed to open last file: {err}"));
+        let mut paths = std::mem::take(&mut app_state.open_files);
+        if paths.is_empty() {
+            paths.extend(app_state.open_file.clone());
+        }
+        for path in paths {
+            match load_creature(&mut app_state, &path) {
+                Ok(document) => documents.push(document),
+                Err(err) => errors.push(err),
             }
         }
 
-        Self {
+        let active_document = documents
+            .iter()
+            .position(|doc| doc.path == app_state.open_file)
+            .unwrap_or(0);
+        let status_message = state_error.map(|err| err.message).or_else(|| {
+            (!errors.is_empty())
+                .then(|| format!("Failed to open last files: {}", errors.join("; ")))
+        });
+
+        let mut library_widget = LibraryWidget::new();
+        let library_folder = app_state
+            .library_folder
+            .clone()
+            .or_else(|| Some(PathBuf::from(DEFAULT_LIBRARY_FOLDER)).filter(|path| path.is_dir()));
+        if let Some(folder) = library_folder {
+            library_widget.scan(ctx, &folder);
+        }
+
+        let mut app = Self {
             app_state,
-            creature_dna,
-            dna_widget: DnaWidget::new(),
+            documents,
+            active_document,
+            pending_close: None,
+            merge_widget: None,
+            library_widget,
+            template_widget: TemplateWidget::new(Path::new(DATA_DIR).join("templates")),
+            command_palette: CommandPalette::new(),
+            preferences_window: PreferencesWindow::new(),
+            show_find: false,
+            panel_layout: PanelLayout::default(),
+            pending_layout: None,
+            workspace_name: String::new(),
+            last_disk_check: 0.0,
             status_message,
+            save_state,
             shutdown_requested,
+        };
+        app.sync_open_files();
+        app
+    }
+
+    fn active_doc(&self) -> Option<&Document> {
+        self.documents.get(self.active_document)
+    }
+
+    fn active_doc_mut(&mut self) -> Option<&mut Document> {
+        self.documents.get_mut(self.active_document)
+    }
+
+    fn document_index(&self, id: DocumentId) -> Option<usize> {
+        self.documents.iter().position(|doc| doc.id() == id)
+    }
+
+    fn sync_open_files(&mut self) {
+        self.app_state.open_files = self
+            .documents
+            .iter()
+            .filter_map(|doc| doc.path.clone())
+            .collect();
+        self.app_state.open_file = self.active_doc().and_then(|doc| doc.path.clone());
+    }
+
+    fn activate(&mut self, idx: usize) {
+        self.active_document = idx.min(self.documents.len().saturating_sub(1));
+        self.sync_open_files();
+    }
+
+    fn open_path(&mut self, path: &Path) {
+        if let Some(idx) = self
+            .documents
+            .iter()
+            .position(|doc| doc.path.as_deref() == Some(path))
+        {
+            self.activate(idx);
+            return;
+        }
+
+        match load_creature(&mut self.app_state, path) {
+            Ok(document) => {
+                self.documents.push(document);
+                self.activate(self.documents.len() - 1);
+                workspace::push_recent(&mut self.app_state.recent_files, path);
+                self.status_message = Some(format!("Loaded {}", path.display()));
+            }
+            Err(err) => {
+                self.status_message = Some(format!("Failed to load file: {err}"));
+            }
         }
     }
 
+    fn new_document(&mut self) {
+        self.documents
+            .push(Document::new(CreatureDNA::default(), None));
+        self.activate(self.documents.len() - 1);
+    }
+
     fn open_file_dialog(&mut self) {
         let mut dialog = rfd::FileDialog::new();
         if let Some(last_folder) = self.app_state.last_folder.as_ref() {
@@ -101,55 +332,80 @@ impl GridworldApp {
         }
 
         if let Some(path) = dialog.pick_file() {
-            match load_creature(&mut self.app_state, &path, &mut self.creature_dna) {
-                Ok(()) => {
-                    self.dna_widget.refresh_from_dna();
-                    self.status_message = Some(format!("Loaded {}", path.display()));
-                }
-                Err(err) => {
-                    self.status_message = Some(format!("Failed to load file: {err}"));
-                }
-            }
+            self.open_path(&path);
         }
     }
 
-    fn save_to_path(&mut self, path: &Path) {
-        let Some(dna) = self.creature_dna.as_ref() else {
-            self.status_message = Some("No DNA loaded to save.".to_string());
+    /// Closes a tab, asking first if it has unsaved changes.
+    fn request_close(&mut self, idx: usize) {
+        match self.documents.get(idx) {
+            Some(doc) if doc.is_dirty() => self.pending_close = Some(doc.id()),
+            Some(_) => self.close_document(idx),
+            None => {}
+        }
+    }
+
+    fn close_document(&mut self, idx: usize) {
+        if idx >= self.documents.len() {
             return;
+        }
+        self.documents.remove(idx);
+        if self.active_document > idx {
+            self.active_document -= 1;
+        }
+        self.activate(self.active_document);
+    }
+
+    fn save_to_path(&mut self, path: &Path) -> bool {
+        let backup_count = self.app_state.preferences.backup_count;
+        let Some(doc) = self.active_doc_mut() else {
+            self.status_message = Some("No DNA loaded to save.".to_string());
+            return false;
         };
 
-        match save_creature(path, dna) {
+        // Encode before touching the backups so a DNA that cannot be written leaves them as is.
+        let saved = encode_creature(path, &doc.dna).and_then(|content| {
+            rotate_backups(path, backup_count)?;
+            write_creature(path, &content)
+        });
+        match saved {
             Ok(()) => {
+                doc.mark_saved(path);
+                workspace::push_recent(&mut self.app_state.recent_files, path);
                 self.status_message = Some(format!("Saved {}", path.display()));
-                self.app_state.open_file = Some(path.to_path_buf());
                 self.app_state.last_folder = path.parent().map(Path::to_path_buf);
+                self.sync_open_files();
+                true
             }
             Err(err) => {
                 self.status_message = Some(format!("Failed to save file: {err}"));
+                false
             }
         }
     }
 
-    fn save_current_file(&mut self) {
-        let Some(path) = self.app_state.open_file.clone() else {
+    fn save_current_file(&mut self) -> bool {
+        let Some(doc) = self.active_doc() else {
             self.status_message = Some("No open file to save.".to_string());
-            return;
+            return false;
         };
-        self.save_to_path(&path);
+        match doc.path.clone() {
+            Some(path) => self.save_to_path(&path),
+            None => self.save_as_file_dialog(),
+        }
     }
 
-    fn save_as_file_dialog(&mut self) {
-        if self.creature_dna.is_none() {
+    fn save_as_file_dialog(&mut self) -> bool {
+        let Some(doc) = self.active_doc() else {
             self.status_message = Some("No DNA loaded to save.".to_string());
-            return;
-        }
+            return false;
+        };
 
         let mut dialog = rfd::FileDialog::new();
         if let Some(last_folder) = self.app_state.last_folder.as_ref() {
             dialog = dialog.set_directory(last_folder);
         }
-        if let Some(open_file) = self.app_state.open_file.as_ref() {
+        if let Some(open_file) = doc.path.as_ref() {
             if let Some(parent) = open_file.parent() {
                 dialog = dialog.set_directory(parent);
             }
@@ -158,15 +414,690 @@ impl GridworldApp {
             }
         }
 
-        if let Some(path) = dialog.save_file() {
-            self.save_to_path(&path);
+        match dialog.save_file() {
+            Some(path) => self.save_to_path(&path),
+            None => false,
+        }
+    }
+
+    fn export_file_dialog(&mut self) {
+        let Some(doc) = self.active_doc() else {
+            self.status_message = Some("No DNA loaded to export.".to_string());
+            return;
+        };
+
+        let mut dialog = rfd::FileDialog::new();
+        for format in [Format::Json, Format::Ron, Format::Yaml] {
+            dialog = dialog.add_filter(format.name(), format.extensions());
+        }
+        if let Some(last_folder) = self.app_state.last_folder.as_ref() {
+            dialog = dialog.set_directory(last_folder);
+        }
+        if let Some(stem) = doc.path.as_deref().and_then(Path::file_stem) {
+            dialog = dialog.set_file_name(format!("{}.json", stem.to_string_lossy()));
+        }
+        let Some(path) = dialog.save_file() else {
+            return;
+        };
+
+        let format = Format::from_path(&path).unwrap_or(Format::Json);
+        let result = exchange::export(&doc.dna, format).and_then(|content| {
+            std::fs::write(&path, content)
+                .map_err(|err| format!("failed to write '{}': {err}", path.display()))
+        });
+        self.status_message = Some(match result {
+            Ok(()) => format!("Exported {}", path.display()),
+            Err(err) => format!("Failed to export: {err}"),
+        });
+    }
+
+    fn export_image_dialog(&mut self) {
+        let Some(doc) = self.active_doc() else {
+            self.status_message = Some("No DNA loaded to export.".to_string());
+            return;
+        };
+
+        let mut dialog = rfd::FileDialog::new()
+            .add_filter("PNG", &["png"])
+            .add_filter("SVG", &["svg"]);
+        if let Some(last_folder) = self.app_state.last_folder.as_ref() {
+            dialog = dialog.set_directory(last_folder);
+        }
+        if let Some(stem) = doc.path.as_deref().and_then(Path::file_stem) {
+            dialog = dialog.set_file_name(format!("{}.png", stem.to_string_lossy()));
+        }
+        let Some(path) = dialog.save_file() else {
+            return;
+        };
+
+        let options = render::RenderOptions {
+            labels: true,
+            arrows: true,
+            ..render::RenderOptions::default()
+        };
+        let result = render::write_image(&path, &doc.dna, &options);
+        self.status_message = Some(match result {
+            Ok(()) => format!("Exported {}", path.display()),
+            Err(err) => format!("Failed to export: {err}"),
+        });
+    }
+
+    /// Writes the output tag network as Graphviz DOT or Mermaid, by the file extension.
+    fn export_graph_dialog(&mut self, clustering: Clustering) {
+        let Some(doc) = self.active_doc() else {
+            self.status_message = Some("No DNA loaded to export.".to_string());
+            return;
+        };
+
+        let mut dialog = rfd::FileDialog::new();
+        for format in GraphFormat::ALL {
+            dialog = dialog.add_filter(format.name(), format.extensions());
+        }
+        if let Some(last_folder) = self.app_state.last_folder.as_ref() {
+            dialog = dialog.set_directory(last_folder);
+        }
+        if let Some(stem) = doc.path.as_deref().and_then(Path::file_stem) {
+            dialog = dialog.set_file_name(format!("{}.dot", stem.to_string_lossy()));
+        }
+        let Some(path) = dialog.save_file() else {
+            return;
+        };
+
+        let format = GraphFormat::from_path(&path).unwrap_or(GraphFormat::Dot);
+        let content = graph::export(&doc.dna, format, clustering);
+        let result = std::fs::write(&path, content)
+            .map_err(|err| format!("failed to write '{}': {err}", path.display()));
+        self.status_message = Some(match result {
+            Ok(()) => format!("Exported {}", path.display()),
+            Err(err) => format!("Failed to export: {err}"),
+        });
+    }
+
+    /// Opens a JSON, RON or YAML creature as a new unsaved tab.
+    fn import_file_dialog(&mut self) {
+        let mut dialog = rfd::FileDialog::new();
+        for format in [Format::Json, Format::Ron, Format::Yaml] {
+            dialog = dialog.add_filter(format.name(), format.extensions());
+        }
+        if let Some(last_folder) = self.app_state.last_folder.as_ref() {
+            dialog = dialog.set_directory(last_folder);
+        }
+        let Some(path) = dialog.pick_file() else {
+            return;
+        };
+
+        let result = std::fs::read_to_string(&path)
+            .map_err(|err| format!("failed to read '{}': {err}", path.display()))
+            .and_then(|content| {
+                let format = Format::from_path(&path).unwrap_or(Format::Json);
+                exchange::import(&content, format)
+            });
+        match result {
+            Ok(dna) => {
+                let mut doc = Document::new(CreatureDNA::default(), None);
+                doc.replace_dna(dna);
+                self.documents.push(doc);
+                self.activate(self.documents.len() - 1);
+                self.status_message = Some(format!("Imported {}", path.display()));
+            }
+            Err(err) => {
+                self.status_message = Some(format!("Failed to import: {err}"));
+            }
+        }
+    }
+
+    fn undo(&mut self) {
+        if let Some(doc) = self.active_doc_mut()
+            && !doc.undo()
+        {
+            self.status_message = Some("Nothing to undo.".to_string());
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(doc) = self.active_doc_mut()
+            && !doc.redo()
+        {
+            self.status_message = Some("Nothing to redo.".to_string());
+        }
+    }
+
+    fn run_action(&mut self, action: Action) {
+        match action {
+            Action::New => self.new_document(),
+            Action::Open => self.open_file_dialog(),
+            Action::Save => {
+                self.save_current_file();
+            }
+            Action::SaveAs => {
+                self.save_as_file_dialog();
+            }
+            Action::Merge => self.merge_file_dialog(),
+            Action::Import => self.import_file_dialog(),
+            Action::Export => self.export_file_dialog(),
+            Action::ExportImage => self.export_image_dialog(),
+            Action::ExportGraph(clustering) => self.export_graph_dialog(clustering),
+            Action::Preferences => self.preferences_window.show(),
+            Action::Close => self.request_close(self.active_document),
+            Action::Undo => self.undo(),
+            Action::Redo => self.redo(),
+            Action::Find => self.show_find = !self.show_find,
+            Action::ToggleLibrary => self.app_state.show_library = !self.app_state.show_library,
+            Action::ToggleTemplates => {
+                self.app_state.show_templates = !self.app_state.show_templates;
+            }
+            Action::ToggleStats => self.app_state.show_stats = !self.app_state.show_stats,
+            Action::CommandPalette => self.command_palette.toggle(),
+            Action::Validate => self.validate_current(),
+            Action::Flip(axis) => {
+                if let Some(doc) = self.active_doc_mut()
+                    && !doc.dna_widget.flip_grid(&mut doc.dna, axis)
+                {
+                    self.status_message = Some("Open the cell grid or a layer to flip.".into());
+                }
+            }
+            Action::MutateSelection => {
+                if let Some(doc) = self.active_doc_mut() {
+                    let message = match doc.dna_widget.mutate_selection(&mut doc.dna) {
+                        Ok(change) => format!("Mutated {change}"),
+                        Err(err) => err,
+                    };
+                    self.status_message = Some(message);
+                }
+            }
+            Action::OpenRecent(idx) => {
+                if let Some(path) = self.app_state.recent_files.get(idx).cloned() {
+                    self.open_path(&path);
+                }
+            }
+            Action::OpenFavorite(idx) => {
+                if let Some(path) = self.app_state.favorite_files.get(idx).cloned() {
+                    self.open_path(&path);
+                }
+            }
+            Action::OpenWorkspace(idx) => self.open_workspace(idx),
+            Action::JumpToCell(idx) => {
+                if let Some(doc) = self.active_doc_mut() {
+                    doc.dna_widget.select(format!("CreatureDNA/cells/{idx}"));
+                }
+            }
+            Action::SwitchLayer { dna_idx, layer_idx } => {
+                if let Some(doc) = self.active_doc_mut() {
+                    doc.dna_widget
+                        .select(format!("CreatureDNA/dna/{dna_idx}/genes/{layer_idx}"));
+                }
+            }
+        }
+    }
+
+    /// Saves the open files, their selections and the panel layout under `name`. Files that
+    /// were never saved are left out.
+    fn save_workspace(&mut self, name: &str) {
+        let name = name.trim();
+        if name.is_empty() {
+            self.status_message = Some("Enter a name for the workspace.".to_string());
+            return;
+        }
+        let saved: Vec<(usize, WorkspaceFile)> = self
+            .documents
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, doc)| {
+                let file = WorkspaceFile {
+                    path: doc.path.clone()?,
+                    selected_path: doc.dna_widget.selected_path().map(str::to_string),
+                };
+                Some((idx, file))
+            })
+            .collect();
+        let active = saved
+            .iter()
+            .position(|(idx, _)| *idx == self.active_document)
+            .unwrap_or(0);
+        let unsaved = self.documents.len() - saved.len();
+        let workspace = Workspace {
+            name: name.to_string(),
+            files: saved.into_iter().map(|(_, file)| file).collect(),
+            active,
+            layout: PanelLayout {
+                show_library: self.app_state.show_library,
+                show_templates: self.app_state.show_templates,
+                show_stats: self.app_state.show_stats,
+                ..self.panel_layout.clone()
+            },
+        };
+        workspace::save_workspace(&mut self.app_state.workspaces, workspace);
+        self.status_message = Some(match unsaved {
+            0 => format!("Saved workspace {name}"),
+            _ => format!("Saved workspace {name} without {unsaved} never saved creature(s)"),
+        });
+    }
+
+    /// Replaces the open files by the ones of a workspace.
+    fn open_workspace(&mut self, idx: usize) {
+        let Some(workspace) = self.app_state.workspaces.get(idx).cloned() else {
+            return;
+        };
+        if self.documents.iter().any(Document::is_dirty) {
+            self.status_message = Some(
+                "Save or close the modified creatures before switching workspaces.".to_string(),
+            );
+            return;
+        }
+
+        self.documents.clear();
+        let mut errors = Vec::new();
+        let mut active = 0;
+        for (file_idx, file) in workspace.files.iter().enumerate() {
+            match load_creature(&mut self.app_state, &file.path) {
+                Ok(mut document) => {
+                    if let Some(selected_path) = file.selected_path.clone() {
+                        document.dna_widget.select(selected_path);
+                    }
+                    if file_idx == workspace.active {
+                        active = self.documents.len();
+                    }
+                    self.documents.push(document);
+                }
+                Err(err) => errors.push(err),
+            }
         }
+        self.activate(active);
+        self.app_state.show_library = workspace.layout.show_library;
+        self.app_state.show_templates = workspace.layout.show_templates;
+        self.app_state.show_stats = workspace.layout.show_stats;
+        self.pending_layout = Some(workspace.layout);
+        self.status_message = Some(if errors.is_empty() {
+            format!("Opened workspace {}", workspace.name)
+        } else {
+            format!(
+                "Opened workspace {} with errors: {}",
+                workspace.name,
+                errors.join("; ")
+            )
+        });
+    }
+
+    /// Favorites first, then the other recent files. The star pins or unpins a file.
+    fn recent_files_ui(&mut self, ui: &mut egui::Ui) {
+        let favorites = &self.app_state.favorite_files;
+        let recent: Vec<&PathBuf> = self
+            .app_state
+            .recent_files
+            .iter()
+            .filter(|path| !favorites.contains(path))
+            .collect();
+        if favorites.is_empty() && recent.is_empty() {
+            ui.label(egui::RichText::new("No recent files").weak());
+            return;
+        }
+
+        let mut open = None;
+        let mut toggle = None;
+        let mut row = |ui: &mut egui::Ui, path: &PathBuf, pinned: bool| {
+            ui.horizontal(|ui| {
+                let star = if pinned { "★" } else { "☆" };
+                let hover = if pinned { "Unpin" } else { "Pin to favorites" };
+                if ui.small_button(star).on_hover_text(hover).clicked() {
+                    toggle = Some(path.clone());
+                }
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| path.display().to_string());
+                if ui
+                    .button(name)
+                    .on_hover_text(path.display().to_string())
+                    .clicked()
+                {
+                    open = Some(path.clone());
+                }
+            });
+        };
+        for path in favorites {
+            row(ui, path, true);
+        }
+        if !favorites.is_empty() && !recent.is_empty() {
+            ui.separator();
+        }
+        for path in recent {
+            row(ui, path, false);
+        }
+
+        ui.separator();
+        if ui.button("Clear Recent").clicked() {
+            self.app_state.recent_files.clear();
+            ui.close();
+        }
+        if let Some(path) = toggle {
+            workspace::toggle_favorite(&mut self.app_state.favorite_files, &path);
+        }
+        if let Some(path) = open {
+            ui.close();
+            self.open_path(&path);
+        }
+    }
+
+    fn workspaces_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.workspace_name)
+                    .hint_text("Workspace name")
+                    .desired_width(160.0),
+            );
+            let can_save = !self.workspace_name.trim().is_empty();
+            if ui
+                .add_enabled(can_save, egui::Button::new("Save"))
+                .on_hover_text("Save the open files, selections and panels under this name")
+                .clicked()
+            {
+                let name = std::mem::take(&mut self.workspace_name);
+                self.save_workspace(&name);
+                ui.close();
+            }
+        });
+        ui.separator();
+
+        if self.app_state.workspaces.is_empty() {
+            ui.label(egui::RichText::new("No saved workspaces").weak());
+            return;
+        }
+        let mut open = None;
+        let mut delete = None;
+        for (idx, workspace) in self.app_state.workspaces.iter().enumerate() {
+            ui.horizontal(|ui| {
+                let files: Vec<String> = workspace
+                    .files
+                    .iter()
+                    .map(|file| file.path.display().to_string())
+                    .collect();
+                if ui
+                    .button(&workspace.name)
+                    .on_hover_text(files.join("\n"))
+                    .clicked()
+                {
+                    open = Some(idx);
+                }
+                if ui
+                    .small_button("🗑")
+                    .on_hover_text("Delete workspace")
+                    .clicked()
+                {
+                    delete = Some(idx);
+                }
+            });
+        }
+        if let Some(idx) = delete {
+            self.app_state.workspaces.remove(idx);
+        }
+        if let Some(idx) = open {
+            ui.close();
+            self.open_workspace(idx);
+        }
+    }
+
+    fn shortcut_text(&self, ctx: &egui::Context, action: Action) -> String {
+        actions::shortcut_text(ctx, &self.app_state.preferences.keybindings, action)
+    }
+
+    fn validate_current(&mut self) {
+        let Some(doc) = self.active_doc() else {
+            self.status_message = Some("No DNA loaded to validate.".to_string());
+            return;
+        };
+        let issues = validate::validate(&doc.dna);
+        self.status_message = Some(match issues.as_slice() {
+            [] => "No issues found.".to_string(),
+            [issue] => format!("1 issue: {issue}"),
+            [issue, ..] => format!("{} issues, first: {issue}", issues.len()),
+        });
+    }
+
+    /// Three-way merge of another version of the open creature into it. The open creature is
+    /// "ours"; the user picks the common ancestor and "their" version.
+    fn merge_file_dialog(&mut self) {
+        let Some((document, ours)) = self.active_doc().map(|doc| (doc.id(), doc.dna.clone()))
+        else {
+            self.status_message = Some("No DNA loaded to merge into.".to_string());
+            return;
+        };
+
+        let mut dialog = rfd::FileDialog::new();
+        if let Some(last_folder) = self.app_state.last_folder.as_ref() {
+            dialog = dialog.set_directory(last_folder);
+        }
+        let Some(base_path) = dialog.clone().set_title("Select merge base").pick_file() else {
+            return;
+        };
+        let Some(theirs_path) = dialog.set_title("Select version to merge").pick_file() else {
+            return;
+        };
+
+        let loaded = read_creature(&base_path)
+            .and_then(|base| read_creature(&theirs_path).map(|theirs| (base, theirs)));
+        let (base, theirs) = match loaded {
+            Ok(loaded) => loaded,
+            Err(err) => {
+                self.status_message = Some(format!("Failed to merge: {err}"));
+                return;
+            }
+        };
+
+        let merge_widget = MergeWidget::new(document, base, ours, theirs, theirs_path.clone());
+        if merge_widget.conflict_count() == 0 {
+            if let Some(doc) = self.active_doc_mut() {
+                doc.replace_dna(merge_widget.merged());
+            }
+            self.status_message = Some(format!("Merged {} cleanly", theirs_path.display()));
+        } else {
+            self.status_message = Some(format!(
+                "{} merge conflict(s) to resolve",
+                merge_widget.conflict_count()
+            ));
+            self.merge_widget = Some(merge_widget);
+        }
+    }
+
+    fn merge_ui(&mut self, ctx: &egui::Context) {
+        let Some(merge_widget) = self.merge_widget.as_mut() else {
+            return;
+        };
+        let Some(action) = merge_widget.ui(ctx) else {
+            return;
+        };
+        let document = merge_widget.document();
+        let disk_version = merge_widget.disk_version().cloned();
+        let doc = self.documents.iter_mut().find(|doc| doc.id() == document);
+        self.status_message = Some(match (action, doc) {
+            (_, None) => "Merge not applied: the creature was closed.".to_string(),
+            (MergeAction::Apply(merged), Some(doc)) if doc.dna == *merge_widget.ours() => {
+                if let Some(disk_version) = disk_version {
+                    doc.accept_disk_version(disk_version);
+                }
+                doc.replace_dna(merged);
+                "Merge applied.".to_string()
+            }
+            (action, Some(doc)) => {
+                // The file on disk still differs from the saved version, so ask again.
+                if let Some(disk_version) = disk_version {
+                    doc.offer_disk_version(disk_version);
+                }
+                match action {
+                    MergeAction::Apply(_) => "Merge not applied: the creature was edited while \
+                                              merging. Merge again."
+                        .to_string(),
+                    MergeAction::Cancel => "Merge cancelled.".to_string(),
+                }
+            }
+        });
+        self.merge_widget = None;
+    }
+
+    /// Looks for open files that were changed outside the editor, e.g. re-exported by the game.
+    fn poll_disk_changes(&mut self, ctx: &egui::Context) {
+        const DISK_CHECK_INTERVAL: f64 = 1.0;
+
+        let now = ctx.input(|i| i.time);
+        if now - self.last_disk_check >= DISK_CHECK_INTERVAL {
+            self.last_disk_check = now;
+            for doc in &mut self.documents {
+                doc.poll_disk();
+            }
+        }
+        ctx.request_repaint_after(std::time::Duration::from_secs_f64(DISK_CHECK_INTERVAL));
+    }
+
+    fn external_change_ui(&mut self, ctx: &egui::Context) {
+        const MAX_LISTED_CHANGES: usize = 30;
+
+        let Some(doc) = self.documents.get_mut(self.active_document) else {
+            return;
+        };
+        let Some(change) = doc.external_change.as_ref() else {
+            return;
+        };
+        let dirty = doc.is_dirty();
+
+        let mut reload = false;
+        let mut merge = false;
+        let mut ignore = false;
+        egui::Window::new("File Changed On Disk")
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} was changed outside the editor:",
+                    doc.path
+                        .as_deref()
+                        .map(|path| path.display().to_string())
+                        .unwrap_or_default()
+                ));
+                for line in change.changes.iter().take(MAX_LISTED_CHANGES) {
+                    ui.label(format!("  {line}"));
+                }
+                if change.changes.len() > MAX_LISTED_CHANGES {
+                    ui.label(format!(
+                        "  ... and {} more",
+                        change.changes.len() - MAX_LISTED_CHANGES
+                    ));
+                }
+                if dirty {
+                    ui.label("You have unsaved changes.");
+                }
+                ui.horizontal(|ui| {
+                    reload = ui
+                        .button(if dirty {
+                            "Reload and discard mine"
+                        } else {
+                            "Reload"
+                        })
+                        .clicked();
+                    if dirty {
+                        merge = ui.button("Merge with mine").clicked();
+                    }
+                    ignore = ui.button("Keep mine").clicked();
+                });
+            });
+
+        if !(reload || merge || ignore) {
+            return;
+        }
+        let Some(change) = doc.external_change.take() else {
+            return;
+        };
+        if merge {
+            // The disk version becomes the saved one only once the merge is applied.
+            let base = doc.saved_dna().clone();
+            let path = doc.path.clone().unwrap_or_default();
+            let merge_widget =
+                MergeWidget::new(doc.id(), base, doc.dna.clone(), change.dna.clone(), path)
+                    .with_disk_version();
+            if merge_widget.conflict_count() == 0 {
+                doc.accept_disk_version(change.dna);
+                doc.replace_dna(merge_widget.merged());
+                self.status_message = Some("Merged changes from disk.".to_string());
+            } else {
+                self.status_message = Some(format!(
+                    "{} merge conflict(s) to resolve",
+                    merge_widget.conflict_count()
+                ));
+                self.merge_widget = Some(merge_widget);
+            }
+            return;
+        }
+        doc.accept_disk_version(change.dna.clone());
+        if reload {
+            doc.replace_dna(change.dna);
+            self.status_message = Some("Reloaded from disk.".to_string());
+        }
+    }
+
+    fn tabs_ui(&mut self, ui: &mut egui::Ui) {
+        let mut activate = None;
+        let mut close = None;
+        ui.horizontal_wrapped(|ui| {
+            for (idx, doc) in self.documents.iter().enumerate() {
+                let response = ui.selectable_label(idx == self.active_document, doc.title());
+                if response.clicked() {
+                    activate = Some(idx);
+                }
+                if response.middle_clicked() || ui.small_button("x").clicked() {
+                    close = Some(idx);
+                }
+                ui.separator();
+            }
+            if ui.small_button("+").on_hover_text("New creature").clicked() {
+                self.new_document();
+            }
+        });
+        if let Some(idx) = activate {
+            self.activate(idx);
+        }
+        if let Some(idx) = close {
+            self.request_close(idx);
+        }
+    }
+
+    fn close_prompt_ui(&mut self, ctx: &egui::Context) {
+        let Some(idx) = self.pending_close.and_then(|id| self.document_index(id)) else {
+            self.pending_close = None;
+            return;
+        };
+        let Some(title) = self.documents.get(idx).map(Document::title) else {
+            self.pending_close = None;
+            return;
+        };
+
+        egui::Window::new("Unsaved Changes")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("{title} has unsaved changes."));
+                ui.horizontal(|ui| {
+                    if ui.button("Save").clicked() {
+                        self.pending_close = None;
+                        self.activate(idx);
+                        if self.save_current_file() {
+                            self.close_document(idx);
+                        }
+                    }
+                    if ui.button("Discard").clicked() {
+                        self.pending_close = None;
+                        self.close_document(idx);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.pending_close = None;
+                    }
+                });
+            });
     }
 }
 
 impl Drop for GridworldApp {
     fn drop(&mut self) {
-        on_exit(&self.app_state);
+        self.sync_open_files();
+        if self.save_state {
+            on_exit(&self.app_state);
+        }
     }
 }
 
@@ -178,13 +1109,50 @@ impl eframe::App for GridworldApp {
             return;
         }
 
+        self.poll_disk_changes(ctx);
+
+        ctx.set_theme(self.app_state.preferences.theme.preference());
+
+        // Text fields keep their own undo and shortcuts, only the palette opens from them.
+        let keybindings = &self.app_state.preferences.keybindings;
+        let mut shortcuts = actions::global_actions(keybindings);
+        if self.preferences_window.is_recording() {
+            shortcuts.clear();
+        } else if ctx.wants_keyboard_input() {
+            shortcuts.retain(|entry| entry.action == Action::CommandPalette);
+        }
+        if let Some(action) = ctx.input_mut(|i| actions::pressed_shortcut(i, &shortcuts)) {
+            self.run_action(action);
+        }
+        if self.command_palette.is_open() {
+            let mut entries = actions::registry(
+                self.active_doc().map(|doc| &doc.dna),
+                &self.app_state.preferences.keybindings,
+            );
+            entries.extend(actions::session_actions(
+                &self.app_state.favorite_files,
+                &self.app_state.recent_files,
+                &self.app_state.workspaces,
+            ));
+            if let Some(action) = self.command_palette.ui(ctx, &entries) {
+                self.run_action(action);
+            }
+        }
+        self.preferences_window
+            .ui(ctx, &mut self.app_state.preferences);
+
         egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
             egui::MenuBar::new().ui(ui, |ui| {
                 ui.menu_button("File", |ui| {
+                    if ui.button("New").clicked() {
+                        ui.close();
+                        self.new_document();
+                    }
                     if ui.button("Open").clicked() {
                         ui.close();
                         self.open_file_dialog();
                     }
+                    ui.menu_button("Open Recent", |ui| self.recent_files_ui(ui));
                     if ui.button("Save").clicked() {
                         self.save_current_file();
                         ui.close();
@@ -193,12 +1161,105 @@ impl eframe::App for GridworldApp {
                         self.save_as_file_dialog();
                         ui.close();
                     }
+                    if ui.button("Merge...").clicked() {
+                        ui.close();
+                        self.merge_file_dialog();
+                    }
+                    ui.separator();
+                    if ui.button("Import...").clicked() {
+                        ui.close();
+                        self.import_file_dialog();
+                    }
+                    if ui.button("Export...").clicked() {
+                        ui.close();
+                        self.export_file_dialog();
+                    }
+                    if ui.button("Export Image...").clicked() {
+                        ui.close();
+                        self.export_image_dialog();
+                    }
+                    ui.menu_button("Export Graph", |ui| {
+                        if ui.button("By Category...").clicked() {
+                            ui.close();
+                            self.export_graph_dialog(Clustering::Category);
+                        }
+                        if ui.button("By Layer...").clicked() {
+                            ui.close();
+                            self.export_graph_dialog(Clustering::Layer);
+                        }
+                    });
+                    ui.separator();
+                    if ui.button("Preferences...").clicked() {
+                        ui.close();
+                        self.preferences_window.show();
+                    }
+                    ui.separator();
+                    if ui.button("Close").clicked() {
+                        ui.close();
+                        self.request_close(self.active_document);
+                    }
                     if ui.button("Quit").clicked() {
                         ui.close();
                         ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                     }
                 });
 
+                ui.menu_button("Edit", |ui| {
+                    let can_undo = self.active_doc().is_some_and(Document::can_undo);
+                    let can_redo = self.active_doc().is_some_and(Document::can_redo);
+                    if ui
+                        .add_enabled(
+                            can_undo,
+                            egui::Button::new("Undo")
+                                .shortcut_text(self.shortcut_text(ctx, Action::Undo)),
+                        )
+                        .clicked()
+                    {
+                        self.undo();
+                        ui.close();
+                    }
+                    if ui
+                        .add_enabled(
+                            can_redo,
+                            egui::Button::new("Redo")
+                                .shortcut_text(self.shortcut_text(ctx, Action::Redo)),
+                        )
+                        .clicked()
+                    {
+                        self.redo();
+                        ui.close();
+                    }
+                    ui.separator();
+                    if ui
+                        .add(
+                            egui::Button::new("Find and Replace...")
+                                .shortcut_text(self.shortcut_text(ctx, Action::Find)),
+                        )
+                        .clicked()
+                    {
+                        self.show_find = true;
+                        ui.close();
+                    }
+                });
+
+                ui.menu_button("Workspaces", |ui| self.workspaces_ui(ui));
+
+                ui.menu_button("View", |ui| {
+                    ui.checkbox(&mut self.app_state.show_library, "Library");
+                    ui.checkbox(&mut self.app_state.show_templates, "Templates");
+                    ui.checkbox(&mut self.app_state.show_stats, "Stats");
+                    if ui
+                        .add(
+                            egui::Button::new("Command Palette")
+                                .shortcut_text(self.shortcut_text(ctx, Action::CommandPalette)),
+                        )
+                        .clicked()
+                    {
+                        ui.close();
+                        self.command_palette.toggle();
+                    }
+                });
+
                 ui.menu_button("Help", |ui| {
                     ui.label("Gridworld Editor");
                 });
@@ -210,24 +1271,116 @@ impl eframe::App for GridworldApp {
             });
         });
 
-        egui::SidePanel::left("sidebar")
-            .default_width(280.0)
-            .resizable(true)
-            .show(ctx, |ui| {
-                ui.heading("Sidebar");
+        egui::TopBottomPanel::top("tab_bar").show(ctx, |ui| {
+            self.tabs_ui(ui);
+        });
+
+        let pending_layout = self.pending_layout.take().unwrap_or_default();
+        if self.app_state.show_library {
+            let mut open = None;
+            let mut panel = egui::SidePanel::right("library")
+                .default_width(300.0)
+                .resizable(true);
+            if let Some(width) = pending_layout.library_width {
+                panel = panel.exact_width(width);
+            }
+            let response = panel.show(ctx, |ui| {
+                ui.heading("Library");
                 ui.separator();
-                self.dna_widget.sidebar_ui(ui, self.creature_dna.as_ref());
+                open = self.library_widget.ui(ui);
             });
+            self.panel_layout.library_width = Some(response.response.rect.width());
+            self.app_state.library_folder = self.library_widget.folder().map(Path::to_path_buf);
+            if let Some(path) = open {
+                self.open_path(&path);
+            }
+        }
+
+        let active_document = self.active_document;
+        let mut doc = self.documents.get_mut(active_document);
+
+        let preferences = &self.app_state.preferences;
+        let mut sidebar = egui::SidePanel::left("sidebar")
+            .default_width(280.0)
+            .resizable(true);
+        if let Some(width) = pending_layout.sidebar_width {
+            sidebar = sidebar.exact_width(width);
+        }
+        let sidebar_response = sidebar.show(ctx, |ui| {
+            ui.heading("Sidebar");
+            ui.separator();
+            match doc.as_deref_mut() {
+                Some(doc) => doc
+                    .dna_widget
+                    .sidebar_ui(ui, Some(&mut doc.dna), preferences),
+                None => DnaWidget::new().sidebar_ui(ui, None, preferences),
+            }
+        });
+        self.panel_layout.sidebar_width = Some(sidebar_response.response.rect.width());
+
+        if self.app_state.show_stats {
+            egui::SidePanel::right("stats")
+                .default_width(260.0)
+                .resizable(true)
+                .show(ctx, |ui| {
+                    ui.heading("Stats");
+                    ui.separator();
+                    stats_widget::stats_ui(
+                        ui,
+                        doc.as_deref().map(|doc| &doc.dna),
+                        &self.app_state.preferences.upkeep_costs,
+                    );
+                });
+        }
 
+        if self.app_state.show_templates {
+            egui::SidePanel::right("templates")
+                .default_width(260.0)
+                .resizable(true)
+                .show(ctx, |ui| {
+                    ui.heading("Templates");
+                    ui.separator();
+                    self.template_widget.ui(ui, doc.as_deref_mut());
+                });
+        }
+
+        let preferences = &mut self.app_state.preferences;
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading("Sub Frame");
             ui.separator();
-            self.dna_widget.detail_ui(ui, self.creature_dna.as_mut());
+            match doc.as_deref_mut() {
+                Some(doc) => doc
+                    .dna_widget
+                    .detail_ui(ui, Some(&mut doc.dna), preferences),
+                None => DnaWidget::new().detail_ui(ui, None, preferences),
+            }
         });
+
+        if let Some(doc) = doc {
+            if self.show_find {
+                egui::Window::new("Find and Replace")
+                    .open(&mut self.show_find)
+                    .default_width(420.0)
+                    .show(ctx, |ui| doc.dna_widget.find_ui(ui, &mut doc.dna));
+            }
+            if !self.show_find {
+                doc.dna_widget.close_find();
+            }
+            doc.commit();
+        }
+
+        self.merge_ui(ctx);
+        self.external_change_ui(ctx);
+        self.close_prompt_ui(ctx);
     }
 }
 
 fn main() -> eframe::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(&args));
+    }
+
     let shutdown_requested = Arc::new(AtomicBool::new(false));
     let shutdown_requested_for_handler = Arc::clone(&shutdown_requested);
     let _ = ctrlc::set_handler(move || {
@@ -245,6 +1398,11 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "Gridworld Editor",
         native_options,
-        Box::new(move |_cc| Ok(Box::new(GridworldApp::new(Arc::clone(&shutdown_requested))))),
+        Box::new(move |cc| {
+            Ok(Box::new(GridworldApp::new(
+                &cc.egui_ctx,
+                Arc::clone(&shutdown_requested),
+            )))
+        }),
     )
 }
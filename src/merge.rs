@@ -0,0 +1,809 @@
+//! Three-way merge of creature files.
+//!
+//! Cells are matched by their grid index, genes by DNA block position, z-level and grid index.
+//! Records sharing one of these keys are matched in file order.
+//! Records changed on only one side are taken over automatically; records changed on both
+//! sides are merged field by field so that only fields of `DecodedGeneInfo` touched by both
+//! sides end up as conflicts.
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::dnaparser::{
+    CreatureDNA, DecodedGeneInfo, DnaData, DnaGenes, GeneRecord, GridIndex2, NeuronProperties,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Side {
+    Base,
+    #[default]
+    Ours,
+    Theirs,
+}
+
+impl Side {
+    pub const ALL: [Side; 3] = [Side::Base, Side::Ours, Side::Theirs];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Side::Base => "base",
+            Side::Ours => "ours",
+            Side::Theirs => "theirs",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sides<T> {
+    pub base: T,
+    pub ours: T,
+    pub theirs: T,
+}
+
+impl<T> Sides<T> {
+    pub fn get(&self, side: Side) -> &T {
+        match side {
+            Side::Base => &self.base,
+            Side::Ours => &self.ours,
+            Side::Theirs => &self.theirs,
+        }
+    }
+}
+
+impl<T: PartialEq> Sides<T> {
+    /// The side to take without asking, or `None` if both sides changed the value differently.
+    fn auto_side(&self) -> Option<Side> {
+        if self.ours == self.theirs || self.theirs == self.base {
+            Some(Side::Ours)
+        } else if self.ours == self.base {
+            Some(Side::Theirs)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RecordLocation {
+    Cell(GridIndex2),
+    Gene {
+        dna_idx: usize,
+        z_level: u16,
+        index: GridIndex2,
+    },
+}
+
+impl fmt::Display for RecordLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordLocation::Cell(index) => write!(f, "cell[{}][{}]", index.x, index.y),
+            RecordLocation::Gene {
+                dna_idx,
+                z_level,
+                index,
+            } => write!(
+                f,
+                "dna {} gene[{}][{}][{}]",
+                dna_idx, index.x, index.y, z_level
+            ),
+        }
+    }
+}
+
+/// A single mergeable field of a record.
+pub(crate) trait MergeField<T>: Copy {
+    fn same(self, a: &T, b: &T) -> bool;
+    fn copy(self, from: &T, to: &mut T);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeneField {
+    NeuronType,
+    Tag,
+    Property(usize),
+    Bias,
+    Ampersand,
+    Mirroring,
+    OutputTags,
+}
+
+impl GeneField {
+    pub const ALL: [GeneField; 14] = [
+        GeneField::NeuronType,
+        GeneField::Tag,
+        GeneField::Property(0),
+        GeneField::Property(1),
+        GeneField::Property(2),
+        GeneField::Property(3),
+        GeneField::Property(4),
+        GeneField::Property(5),
+        GeneField::Property(6),
+        GeneField::Property(7),
+        GeneField::Bias,
+        GeneField::Ampersand,
+        GeneField::Mirroring,
+        GeneField::OutputTags,
+    ];
+
+    pub fn name(self) -> String {
+        match self {
+            GeneField::NeuronType => "neuron type".to_string(),
+            GeneField::Tag => "tag".to_string(),
+            GeneField::Property(idx) => format!("property {idx}"),
+            GeneField::Bias => "bias".to_string(),
+            GeneField::Ampersand => "ampersand".to_string(),
+            GeneField::Mirroring => "mirroring".to_string(),
+            GeneField::OutputTags => "output tags".to_string(),
+        }
+    }
+
+    pub fn describe(self, info: &DecodedGeneInfo) -> String {
+        match self {
+            GeneField::NeuronType => format!(
+                "{} ({})",
+                info.neuron_type.to_name(),
+                info.neuron_type.to_char()
+            ),
+            GeneField::Tag => info.tag.to_char(),
+            GeneField::Property(idx) => {
+                let prop = info.properties[idx];
+                format!("{} ({})", prop.to_char(), prop.to_int())
+            }
+            GeneField::Bias => format!("{:.3}", info.bias.0.as_bias()),
+            GeneField::Ampersand => info
+                .ampersand
+                .map(|ampersand| ampersand.to_char())
+                .unwrap_or_else(|| "-".to_string()),
+            GeneField::Mirroring => info
+                .mirroring
+                .map(|mirroring| mirroring.to_mirror())
+                .unwrap_or_else(|| "-".to_string()),
+            GeneField::OutputTags => {
+                let tags: Vec<String> = info
+                    .output_tags
+                    .iter()
+                    .map(|tag| format!("{}:{:.3}", tag.to_char(), tag.weight.as_weight()))
+                    .collect();
+                if tags.is_empty() {
+                    "-".to_string()
+                } else {
+                    tags.join(" ")
+                }
+            }
+        }
+    }
+}
+
+impl MergeField<DecodedGeneInfo> for GeneField {
+    fn same(self, a: &DecodedGeneInfo, b: &DecodedGeneInfo) -> bool {
+        match self {
+            GeneField::NeuronType => a.neuron_type == b.neuron_type,
+            GeneField::Tag => a.tag == b.tag,
+            GeneField::Property(idx) => a.properties[idx] == b.properties[idx],
+            GeneField::Bias => a.bias == b.bias,
+            GeneField::Ampersand => a.ampersand == b.ampersand,
+            GeneField::Mirroring => a.mirroring == b.mirroring,
+            GeneField::OutputTags => a.output_tags == b.output_tags,
+        }
+    }
+
+    fn copy(self, from: &DecodedGeneInfo, to: &mut DecodedGeneInfo) {
+        match self {
+            GeneField::NeuronType => to.neuron_type = from.neuron_type,
+            GeneField::Tag => to.tag = from.tag,
+            GeneField::Property(idx) => to.properties[idx] = from.properties[idx],
+            GeneField::Bias => to.bias = from.bias,
+            GeneField::Ampersand => to.ampersand = from.ampersand,
+            GeneField::Mirroring => to.mirroring = from.mirroring,
+            GeneField::OutputTags => to.output_tags = from.output_tags.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextField {
+    Name,
+    Date,
+    Version,
+    SkinColor,
+}
+
+impl TextField {
+    pub const ALL: [TextField; 4] = [
+        TextField::Name,
+        TextField::Date,
+        TextField::Version,
+        TextField::SkinColor,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TextField::Name => "name",
+            TextField::Date => "date",
+            TextField::Version => "version",
+            TextField::SkinColor => "skin_color",
+        }
+    }
+
+    pub fn get(self, dna: &CreatureDNA) -> &Option<String> {
+        match self {
+            TextField::Name => &dna.metadata.name,
+            TextField::Date => &dna.metadata.date,
+            TextField::Version => &dna.metadata.version,
+            TextField::SkinColor => &dna.creature.skin_color,
+        }
+    }
+
+    fn get_mut(self, dna: &mut CreatureDNA) -> &mut Option<String> {
+        match self {
+            TextField::Name => &mut dna.metadata.name,
+            TextField::Date => &mut dna.metadata.date,
+            TextField::Version => &mut dna.metadata.version,
+            TextField::SkinColor => &mut dna.creature.skin_color,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DnaField {
+    CommentName,
+    Name,
+    Location,
+    Creator,
+}
+
+impl DnaField {
+    pub const ALL: [DnaField; 4] = [
+        DnaField::CommentName,
+        DnaField::Name,
+        DnaField::Location,
+        DnaField::Creator,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DnaField::CommentName => "dna_comment_name",
+            DnaField::Name => "dna_name",
+            DnaField::Location => "dna_location",
+            DnaField::Creator => "dna_creator",
+        }
+    }
+
+    pub fn describe(self, block: &DnaData) -> String {
+        let text = match self {
+            DnaField::CommentName => block.dna_comment_name.clone(),
+            DnaField::Name => block
+                .dna_name
+                .as_ref()
+                .map(|record| format!("[{}][{}] {}", record.index.x, record.index.y, record.name)),
+            DnaField::Location => block
+                .dna_location
+                .map(|location| format!("[{}][{}]", location.x, location.y)),
+            DnaField::Creator => block.dna_creator.as_ref().map(|record| {
+                format!(
+                    "[{}][{}] {}",
+                    record.index.x, record.index.y, record.creator
+                )
+            }),
+        };
+        text.unwrap_or_else(|| "-".to_string())
+    }
+}
+
+impl MergeField<DnaData> for DnaField {
+    fn same(self, a: &DnaData, b: &DnaData) -> bool {
+        match self {
+            DnaField::CommentName => a.dna_comment_name == b.dna_comment_name,
+            DnaField::Name => a.dna_name == b.dna_name,
+            DnaField::Location => a.dna_location == b.dna_location,
+            DnaField::Creator => a.dna_creator == b.dna_creator,
+        }
+    }
+
+    fn copy(self, from: &DnaData, to: &mut DnaData) {
+        match self {
+            DnaField::CommentName => to.dna_comment_name = from.dna_comment_name.clone(),
+            DnaField::Name => to.dna_name = from.dna_name.clone(),
+            DnaField::Location => to.dna_location = from.dna_location,
+            DnaField::Creator => to.dna_creator = from.dna_creator.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conflict {
+    /// Metadata or creature level string changed on both sides.
+    Text {
+        field: TextField,
+        sides: Sides<Option<String>>,
+    },
+    Comments(Sides<Vec<String>>),
+    /// A whole DNA block was added on both sides, or deleted on one side and edited on the other.
+    DnaBlock {
+        dna_idx: usize,
+        sides: Sides<Option<DnaData>>,
+    },
+    /// A header field of a DNA block changed on both sides. Genes are not part of `sides`.
+    DnaField {
+        dna_idx: usize,
+        field: DnaField,
+        sides: Sides<DnaData>,
+    },
+    /// A cell or gene was added on both sides, or deleted on one side and edited on the other.
+    Record {
+        location: RecordLocation,
+        sides: Sides<Option<DecodedGeneInfo>>,
+    },
+    /// A single field of a cell or gene changed on both sides.
+    GeneField {
+        location: RecordLocation,
+        field: GeneField,
+        sides: Sides<DecodedGeneInfo>,
+    },
+}
+
+impl Conflict {
+    pub fn title(&self) -> String {
+        match self {
+            Conflict::Text { field, .. } => field.name().to_string(),
+            Conflict::Comments(_) => "comments".to_string(),
+            Conflict::DnaBlock { dna_idx, .. } => format!("dna {dna_idx}"),
+            Conflict::DnaField { dna_idx, field, .. } => {
+                format!("dna {dna_idx} {}", field.name())
+            }
+            Conflict::Record { location, .. } => location.to_string(),
+            Conflict::GeneField {
+                location, field, ..
+            } => format!("{location} {}", field.name()),
+        }
+    }
+
+    pub fn side_text(&self, side: Side) -> String {
+        const ABSENT: &str = "(absent)";
+        match self {
+            Conflict::Text { sides, .. } => sides
+                .get(side)
+                .clone()
+                .unwrap_or_else(|| ABSENT.to_string()),
+            Conflict::Comments(sides) => sides.get(side).join(" / "),
+            Conflict::DnaBlock { sides, .. } => match sides.get(side) {
+                Some(block) => format!(
+                    "{} ({} genes)",
+                    DnaField::Name.describe(block),
+                    block.genes.gene_count()
+                ),
+                None => ABSENT.to_string(),
+            },
+            Conflict::DnaField { field, sides, .. } => field.describe(sides.get(side)),
+            Conflict::Record { sides, .. } => match sides.get(side) {
+                Some(info) => info.encode().unwrap_or_else(|err| err),
+                None => ABSENT.to_string(),
+            },
+            Conflict::GeneField { field, sides, .. } => field.describe(sides.get(side)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MergeOutcome {
+    pub merged: CreatureDNA,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Merges `ours` and `theirs` relative to `base`, resolving every conflict in favor of `ours`.
+pub fn merge(base: &CreatureDNA, ours: &CreatureDNA, theirs: &CreatureDNA) -> MergeOutcome {
+    merge_with(base, ours, theirs, |_, _| Side::Ours)
+}
+
+/// Merges `ours` and `theirs` relative to `base`.
+///
+/// `resolve` is called with the index and description of every conflict, in a deterministic
+/// order, and picks the side to use. Merging the same inputs again with different answers
+/// therefore yields the same conflict list, which is how the conflict view applies choices.
+pub fn merge_with<F>(
+    base: &CreatureDNA,
+    ours: &CreatureDNA,
+    theirs: &CreatureDNA,
+    resolve: F,
+) -> MergeOutcome
+where
+    F: FnMut(usize, &Conflict) -> Side,
+{
+    let mut merger = Merger {
+        resolve,
+        conflicts: Vec::new(),
+    };
+    let mut merged = CreatureDNA::default();
+
+    for field in TextField::ALL {
+        let sides = Sides {
+            base: field.get(base).clone(),
+            ours: field.get(ours).clone(),
+            theirs: field.get(theirs).clone(),
+        };
+        let side = match sides.auto_side() {
+            Some(side) => side,
+            None => merger.conflict(Conflict::Text {
+                field,
+                sides: sides.clone(),
+            }),
+        };
+        *field.get_mut(&mut merged) = sides.get(side).clone();
+    }
+
+    merged.cells.0 = merger.merge_cells(base, ours, theirs);
+
+    let block_count = base.dna.len().max(ours.dna.len()).max(theirs.dna.len());
+    for dna_idx in 0..block_count {
+        let sides = Sides {
+            base: base.dna.get(dna_idx),
+            ours: ours.dna.get(dna_idx),
+            theirs: theirs.dna.get(dna_idx),
+        };
+        if let Some(block) = merger.merge_block(dna_idx, sides) {
+            merged.dna.push(block);
+        }
+    }
+
+    let sides = Sides {
+        base: base.comments.clone(),
+        ours: ours.comments.clone(),
+        theirs: theirs.comments.clone(),
+    };
+    let side = match sides.auto_side() {
+        Some(side) => side,
+        None => merger.conflict(Conflict::Comments(sides.clone())),
+    };
+    merged.comments = sides.get(side).clone();
+
+    MergeOutcome {
+        merged,
+        conflicts: merger.conflicts,
+    }
+}
+
+struct Merger<F> {
+    resolve: F,
+    conflicts: Vec<Conflict>,
+}
+
+impl<F> Merger<F>
+where
+    F: FnMut(usize, &Conflict) -> Side,
+{
+    fn conflict(&mut self, conflict: Conflict) -> Side {
+        let side = (self.resolve)(self.conflicts.len(), &conflict);
+        self.conflicts.push(conflict);
+        side
+    }
+
+    fn merge_cells<'a>(
+        &mut self,
+        base: &'a CreatureDNA,
+        ours: &'a CreatureDNA,
+        theirs: &'a CreatureDNA,
+    ) -> Vec<NeuronProperties> {
+        let keys = ordered_keys(
+            numbered(ours.cells.iter().map(|cell| cell.index)),
+            numbered(theirs.cells.iter().map(|cell| cell.index)),
+        );
+        let nth_cell = |dna: &'a CreatureDNA, index: GridIndex2, nth: usize| {
+            dna.cells
+                .iter()
+                .filter(|cell| cell.index == index)
+                .nth(nth)
+                .map(|cell| &cell.decoded)
+        };
+        let mut out = Vec::with_capacity(keys.len());
+        for (index, nth) in keys {
+            let sides = Sides {
+                base: nth_cell(base, index, nth),
+                ours: nth_cell(ours, index, nth),
+                theirs: nth_cell(theirs, index, nth),
+            };
+            if let Some(decoded) = self.merge_record(RecordLocation::Cell(index), sides) {
+                out.push(NeuronProperties { index, decoded });
+            }
+        }
+        out
+    }
+
+    fn merge_block<'a>(
+        &mut self,
+        dna_idx: usize,
+        sides: Sides<Option<&'a DnaData>>,
+    ) -> Option<DnaData> {
+        let (Some(base), Some(ours), Some(theirs)) = (sides.base, sides.ours, sides.theirs) else {
+            let owned = Sides {
+                base: sides.base.cloned(),
+                ours: sides.ours.cloned(),
+                theirs: sides.theirs.cloned(),
+            };
+            let side = match owned.auto_side() {
+                Some(side) => side,
+                None => self.conflict(Conflict::DnaBlock {
+                    dna_idx,
+                    sides: owned.clone(),
+                }),
+            };
+            return owned.get(side).clone();
+        };
+
+        let header = |block: &DnaData| DnaData {
+            genes: DnaGenes::default(),
+            ..block.clone()
+        };
+        let headers = Sides {
+            base: header(base),
+            ours: header(ours),
+            theirs: header(theirs),
+        };
+        let mut out = merge_fields(&DnaField::ALL, &headers, |field| {
+            self.conflict(Conflict::DnaField {
+                dna_idx,
+                field,
+                sides: headers.clone(),
+            })
+        });
+
+        let gene_keys = |block: &DnaData| -> Vec<(u16, GridIndex2)> {
+            block
+                .genes
+                .iter()
+                .flat_map(|layer| layer.genes.iter().map(|gene| (layer.z_level, gene.index)))
+                .collect()
+        };
+        let nth_gene = |block: &'a DnaData, z_level: u16, index: GridIndex2, nth: usize| {
+            block
+                .genes
+                .iter()
+                .filter(|layer| layer.z_level == z_level)
+                .flat_map(|layer| layer.genes.iter())
+                .filter(|gene| gene.index == index)
+                .nth(nth)
+                .map(|gene| &gene.decoded)
+        };
+        let keys = ordered_keys(numbered(gene_keys(ours)), numbered(gene_keys(theirs)));
+        for ((z_level, index), nth) in keys {
+            let location = RecordLocation::Gene {
+                dna_idx,
+                z_level,
+                index,
+            };
+            let sides = Sides {
+                base: nth_gene(base, z_level, index, nth),
+                ours: nth_gene(ours, z_level, index, nth),
+                theirs: nth_gene(theirs, z_level, index, nth),
+            };
+            if let Some(decoded) = self.merge_record(location, sides) {
+                out.genes.push_gene(z_level, GeneRecord { index, decoded });
+            }
+        }
+
+        Some(out)
+    }
+
+    fn merge_record(
+        &mut self,
+        location: RecordLocation,
+        sides: Sides<Option<&DecodedGeneInfo>>,
+    ) -> Option<DecodedGeneInfo> {
+        if let (Some(base), Some(ours), Some(theirs)) = (sides.base, sides.ours, sides.theirs) {
+            let sides = Sides {
+                base: base.clone(),
+                ours: ours.clone(),
+                theirs: theirs.clone(),
+            };
+            return Some(merge_fields(&GeneField::ALL, &sides, |field| {
+                self.conflict(Conflict::GeneField {
+                    location,
+                    field,
+                    sides: sides.clone(),
+                })
+            }));
+        }
+
+        let owned = Sides {
+            base: sides.base.cloned(),
+            ours: sides.ours.cloned(),
+            theirs: sides.theirs.cloned(),
+        };
+        let side = match owned.auto_side() {
+            Some(side) => side,
+            None => self.conflict(Conflict::Record {
+                location,
+                sides: owned.clone(),
+            }),
+        };
+        owned.get(side).clone()
+    }
+}
+
+fn merge_fields<T, Fd, C>(fields: &[Fd], sides: &Sides<T>, mut on_conflict: C) -> T
+where
+    T: Clone,
+    Fd: MergeField<T>,
+    C: FnMut(Fd) -> Side,
+{
+    let mut out = sides.ours.clone();
+    for &field in fields {
+        let ours_changed = !field.same(&sides.base, &sides.ours);
+        let theirs_changed = !field.same(&sides.base, &sides.theirs);
+        let side = match (ours_changed, theirs_changed) {
+            (_, false) => Side::Ours,
+            (false, true) => Side::Theirs,
+            (true, true) if field.same(&sides.ours, &sides.theirs) => Side::Ours,
+            (true, true) => on_conflict(field),
+        };
+        field.copy(sides.get(side), &mut out);
+    }
+    out
+}
+
+/// Keys of `ours` in order, followed by keys only present in `theirs`.
+pub(crate) fn ordered_keys<K, O, T>(ours: O, theirs: T) -> Vec<K>
+where
+    K: Copy + Eq + std::hash::Hash,
+    O: IntoIterator<Item = K>,
+    T: IntoIterator<Item = K>,
+{
+    let mut seen = HashSet::new();
+    ours.into_iter()
+        .chain(theirs)
+        .filter(|key| seen.insert(*key))
+        .collect()
+}
+
+/// Pairs each key with the number of times it came before, so that records sharing a key
+/// stay apart.
+fn numbered<K>(keys: impl IntoIterator<Item = K>) -> Vec<(K, usize)>
+where
+    K: Copy + Eq + std::hash::Hash,
+{
+    let mut counts = HashMap::new();
+    keys.into_iter()
+        .map(|key| {
+            let count = counts.entry(key).or_insert(0);
+            *count += 1;
+            (key, *count - 1)
+        })
+        .collect()
+}
+
+pub(crate) fn find_gene(
+    block: &DnaData,
+    z_level: u16,
+    index: GridIndex2,
+) -> Option<&DecodedGeneInfo> {
+    block
+        .genes
+        .iter()
+        .filter(|layer| layer.z_level == z_level)
+        .flat_map(|layer| layer.genes.iter())
+        .find(|gene| gene.index == index)
+        .map(|gene| &gene.decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dnaparser::{PropertyValue, parse_creature_dna};
+
+    fn e5() -> CreatureDNA {
+        let input = std::fs::read_to_string("data/e5.txt").expect("failed to read data/e5.txt");
+        parse_creature_dna(&input).expect("failed to parse e5")
+    }
+
+    #[test]
+    fn identical_inputs_merge_cleanly() {
+        let base = e5();
+        let outcome = merge(&base, &base, &base);
+        assert!(outcome.conflicts.is_empty());
+        assert_eq!(outcome.merged, base);
+    }
+
+    #[test]
+    fn disjoint_edits_are_combined() {
+        let base = e5();
+        let mut ours = base.clone();
+        let mut theirs = base.clone();
+        ours.cells[0].decoded.properties[0].0.increase();
+        theirs.cells[0].decoded.bias.0.increase();
+        theirs.cells.remove(1);
+        theirs.metadata.name = Some("renamed".to_string());
+
+        let outcome = merge(&base, &ours, &theirs);
+        assert!(outcome.conflicts.is_empty());
+
+        let mut expected = ours.clone();
+        expected.cells[0].decoded.bias.0.increase();
+        expected.cells.remove(1);
+        expected.metadata.name = Some("renamed".to_string());
+        assert_eq!(outcome.merged, expected);
+    }
+
+    #[test]
+    fn same_field_edits_conflict_per_field() {
+        let base = e5();
+        let mut ours = base.clone();
+        let mut theirs = base.clone();
+        ours.cells[0].decoded.properties[2] =
+            crate::dnaparser::GeneProperty(PropertyValue::new(1).expect("valid value"));
+        theirs.cells[0].decoded.properties[2] =
+            crate::dnaparser::GeneProperty(PropertyValue::new(2).expect("valid value"));
+        theirs.cells[0].decoded.tag.0.increase();
+
+        let outcome = merge(&base, &ours, &theirs);
+        assert_eq!(outcome.conflicts.len(), 1);
+        assert!(matches!(
+            outcome.conflicts[0],
+            Conflict::GeneField {
+                field: GeneField::Property(2),
+                ..
+            }
+        ));
+        assert_eq!(outcome.merged.cells[0].decoded.properties[2].0.raw(), 1);
+        assert_eq!(
+            outcome.merged.cells[0].decoded.tag,
+            theirs.cells[0].decoded.tag
+        );
+
+        let resolved = merge_with(&base, &ours, &theirs, |_, _| Side::Theirs);
+        assert_eq!(resolved.merged.cells[0].decoded, theirs.cells[0].decoded);
+    }
+
+    #[test]
+    fn delete_against_edit_is_a_record_conflict() {
+        let base = e5();
+        let mut ours = base.clone();
+        let mut theirs = base.clone();
+        let index = base.dna[0].genes[0].genes[0].index;
+        ours.dna[0].genes[0].genes.remove(0);
+        theirs.dna[0].genes[0].genes[0].decoded.bias.0.increase();
+
+        let outcome = merge(&base, &ours, &theirs);
+        assert_eq!(outcome.conflicts.len(), 1);
+        assert!(matches!(
+            &outcome.conflicts[0],
+            Conflict::Record {
+                location: RecordLocation::Gene { dna_idx: 0, index: at, .. },
+                sides,
+            } if *at == index && sides.ours.is_none()
+        ));
+        assert_eq!(outcome.merged.dna[0], ours.dna[0]);
+
+        let resolved = merge_with(&base, &ours, &theirs, |_, _| Side::Theirs);
+        assert_eq!(
+            resolved.merged.dna[0].genes.gene_count(),
+            base.dna[0].genes.gene_count()
+        );
+    }
+
+    #[test]
+    fn cells_sharing_an_index_are_merged_in_order() {
+        let mut base = e5();
+        let mut duplicate = base.cells[0].clone();
+        duplicate.decoded.bias.0.increase();
+        base.cells.push(duplicate);
+        let mut ours = base.clone();
+        let mut theirs = base.clone();
+        ours.cells[0].decoded.properties[0].0.increase();
+        let last = theirs.cells.len() - 1;
+        theirs.cells[last].decoded.properties[1].0.increase();
+
+        let outcome = merge(&base, &ours, &theirs);
+        assert!(outcome.conflicts.is_empty());
+        assert_eq!(outcome.merged.cells.len(), base.cells.len());
+        let index = base.cells[0].index;
+        let merged: Vec<&DecodedGeneInfo> = outcome
+            .merged
+            .cells
+            .iter()
+            .filter(|cell| cell.index == index)
+            .map(|cell| &cell.decoded)
+            .collect();
+        assert_eq!(
+            merged,
+            [&ours.cells[0].decoded, &theirs.cells[last].decoded]
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use std::path::PathBuf;
+
+use egui::{RichText, ScrollArea};
+
+use crate::dnaparser::CreatureDNA;
+use crate::document::DocumentId;
+use crate::merge::{self, Conflict, Side};
+
+pub enum MergeAction {
+    Apply(CreatureDNA),
+    Cancel,
+}
+
+/// Conflict resolution view for a three-way merge into an open creature.
+pub struct MergeWidget {
+    /// The document merged into. The window doesn't block the tabs, so it may be closed or
+    /// edited before the merge is applied.
+    document: DocumentId,
+    /// Version on disk merged in, which becomes the saved version once the merge is applied.
+    disk_version: Option<CreatureDNA>,
+    theirs_path: PathBuf,
+    base: CreatureDNA,
+    ours: CreatureDNA,
+    theirs: CreatureDNA,
+    conflicts: Vec<Conflict>,
+    choices: Vec<Side>,
+}
+
+impl MergeWidget {
+    pub fn new(
+        document: DocumentId,
+        base: CreatureDNA,
+        ours: CreatureDNA,
+        theirs: CreatureDNA,
+        theirs_path: PathBuf,
+    ) -> Self {
+        let conflicts = merge::merge(&base, &ours, &theirs).conflicts;
+        let choices = vec![Side::Ours; conflicts.len()];
+        Self {
+            document,
+            disk_version: None,
+            theirs_path,
+            base,
+            ours,
+            theirs,
+            conflicts,
+            choices,
+        }
+    }
+
+    pub fn document(&self) -> DocumentId {
+        self.document
+    }
+
+    /// Merge of a version of the document's file changed on disk.
+    pub fn with_disk_version(mut self) -> Self {
+        self.disk_version = Some(self.theirs.clone());
+        self
+    }
+
+    pub fn disk_version(&self) -> Option<&CreatureDNA> {
+        self.disk_version.as_ref()
+    }
+
+    /// The document's creature when the merge started.
+    pub fn ours(&self) -> &CreatureDNA {
+        &self.ours
+    }
+
+    pub fn conflict_count(&self) -> usize {
+        self.conflicts.len()
+    }
+
+    pub fn merged(&self) -> CreatureDNA {
+        merge::merge_with(&self.base, &self.ours, &self.theirs, |idx, _| {
+            self.choices.get(idx).copied().unwrap_or_default()
+        })
+        .merged
+    }
+
+    pub fn ui(&mut self, ctx: &egui::Context) -> Option<MergeAction> {
+        let mut action = None;
+        egui::Window::new("Resolve Merge Conflicts")
+            .default_width(640.0)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} conflict(s) merging {}",
+                    self.conflicts.len(),
+                    self.theirs_path.display()
+                ));
+                ui.horizontal(|ui| {
+                    for side in Side::ALL {
+                        if ui.button(format!("Use all {}", side.name())).clicked() {
+                            self.choices.fill(side);
+                        }
+                    }
+                });
+                ui.separator();
+
+                ScrollArea::vertical().max_height(480.0).show(ui, |ui| {
+                    for (idx, conflict) in self.conflicts.iter().enumerate() {
+                        ui.push_id(("merge_conflict", idx), |ui| {
+                            ui.label(RichText::new(conflict.title()).strong());
+                            for side in Side::ALL {
+                                let text = format!("{}: {}", side.name(), conflict.side_text(side));
+                                ui.radio_value(&mut self.choices[idx], side, text);
+                            }
+                        });
+                        ui.separator();
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    if ui.button("Apply").clicked() {
+                        action = Some(MergeAction::Apply(self.merged()));
+                    }
+                    if ui.button("Cancel").clicked() {
+                        action = Some(MergeAction::Cancel);
+                    }
+                });
+            });
+        action
+    }
+}
@@ -1,11 +1,11 @@
 /// While for most of this editor I was pretty involved this is pure vibes based on the manual.
-use crate::dnaparser::{GeneProperty, PropertyValue};
+use crate::dnaparser::{GeneProperty, PropertyValue, PropertyValueRepresentation};
 
 impl GeneProperty {
     #[allow(dead_code)]
     pub fn prop_info(&mut self, neuron_type: u8, property_number: u8) -> Option<String> {
         let _ = self;
-        let neuron_char = PropertyValue { raw: neuron_type }.to_char()?;
+        let neuron_char = PropertyValue::new(neuron_type)?.to_char()?;
         // Some call sites use 0-based property indices; the manual uses Property 0..7.
         // Expose 1-based as requested, while tolerating 0 as Property 1.
         lookup_prop_info(neuron_char, property_number).map(str::to_string)
@@ -131,3 +131,139 @@ pub(crate) fn lookup_prop_info(neuron_char: char, prop_n: u8) -> Option<&'static
         _ => None,
     }
 }
+
+/// Property numbers holding the red, green and blue channel of a color-bearing neuron.
+pub(crate) fn color_channel_props(neuron_char: char) -> Option<[u8; 3]> {
+    let find = |channel: &str| {
+        (0..8).find(|&prop_n| {
+            lookup_prop_info(neuron_char, prop_n)
+                .is_some_and(|info| info.split(' ').any(|word| word == channel))
+        })
+    };
+    Some([find("red")?, find("green")?, find("blue")?])
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum NeuronCategory {
+    Sensor,
+    Actuator,
+    Logic,
+    Structure,
+    Other,
+}
+
+impl NeuronCategory {
+    pub(crate) const ALL: [Self; 5] = [
+        Self::Sensor,
+        Self::Actuator,
+        Self::Logic,
+        Self::Structure,
+        Self::Other,
+    ];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Sensor => "sensors",
+            Self::Actuator => "actuators",
+            Self::Logic => "logic",
+            Self::Structure => "structure",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// Rough grouping for pickers and statistics: sensors produce signals from the world, actuators
+/// act on it, logic only processes signals and structure cells mostly just exist.
+pub(crate) fn neuron_category(neuron_char: char) -> NeuronCategory {
+    use NeuronCategory::*;
+    match neuron_char {
+        'A' | 'F' | 'I' | 'M' | 'N' | 'R' | 'V' | 'b' | 'd' | 'f' | 'i' | 'p' | 'q' | '7' => Sensor,
+        'B' | 'E' | 'O' | 'S' | 'T' | 'U' | 'W' | 'X' | 'Y' | 'Z' | 'a' | 'c' | 'g' | 'h' | 'l'
+        | 'm' | 'r' | 's' | 'v' | 'w' | 'z' | '1' | '4' | '5' | '6' | '8' | '9' => Actuator,
+        'G' | 'K' | 'L' | 'P' | 'Q' | 'e' | 'n' | 'o' | 't' | 'u' | '2' | '3' => Logic,
+        'C' | 'D' | 'H' | 'J' | 'j' | 'k' | 'x' | 'y' | '0' => Structure,
+        _ => Other,
+    }
+}
+
+/// All known property descriptions of a neuron kind on one line.
+pub(crate) fn property_summary(neuron_char: char) -> String {
+    let infos: Vec<String> = (0..8)
+        .filter_map(|prop_n| {
+            lookup_prop_info(neuron_char, prop_n).map(|info| format!("{prop_n}: {info}"))
+        })
+        .collect();
+    if infos.is_empty() {
+        "no properties".to_string()
+    } else {
+        infos.join(", ")
+    }
+}
+
+/// How a property of a neuron kind is read: thresholds as 0..2.5, chances, factors and
+/// normalized values as 0..1, everything else (modes, channels, tags, z-levels) as an integer.
+pub(crate) fn property_representation(
+    neuron_char: char,
+    prop_n: u8,
+) -> PropertyValueRepresentation {
+    use PropertyValueRepresentation::*;
+    match (neuron_char, prop_n) {
+        ('R', 1) | ('b', 0) | ('d', 0) | ('q', 0) => PThreshold,
+        ('N', 0 | 1) | ('a', 3 | 4) | ('n' | 'o', 0) => PFloat,
+        _ => PInt,
+    }
+}
+
+/// Property values for a freshly created neuron of a kind. Colors start as mid grey, chances at
+/// one half and the energy sensor covers the whole range; everything else is zero.
+pub(crate) fn default_properties(neuron_char: char) -> [u8; 8] {
+    let mut props = [0; 8];
+    if let Some(channels) = color_channel_props(neuron_char) {
+        for prop_n in channels {
+            props[usize::from(prop_n)] = 32;
+        }
+    }
+    match neuron_char {
+        'n' | 'o' => props[0] = 32,
+        'N' => props[1] = 63,
+        _ => {}
+    }
+    props
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_channels_of_color_bearing_neurons() {
+        for neuron_char in ['E', 'Z', 'a', 'f', 'k', 'v', 'w'] {
+            assert_eq!(color_channel_props(neuron_char), Some([0, 1, 2]));
+        }
+        assert_eq!(color_channel_props('R'), None);
+        assert_eq!(color_channel_props('j'), None);
+    }
+
+    #[test]
+    fn categories_and_defaults() {
+        assert_eq!(neuron_category('R'), NeuronCategory::Sensor);
+        assert_eq!(neuron_category('T'), NeuronCategory::Actuator);
+        assert_eq!(neuron_category('e'), NeuronCategory::Logic);
+        assert_eq!(neuron_category('D'), NeuronCategory::Structure);
+        assert_eq!(neuron_category('!'), NeuronCategory::Other);
+        assert_eq!(default_properties('k'), [32, 32, 32, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            property_representation('R', 1),
+            PropertyValueRepresentation::PThreshold
+        );
+        assert_eq!(
+            property_representation('R', 0),
+            PropertyValueRepresentation::PInt
+        );
+        assert_eq!(property_summary('j'), "no properties");
+        assert_eq!(
+            property_summary('K'),
+            "0: z-index of genes in the DNA to copy, 1: build-after-copy flag"
+        );
+    }
+}
@@ -0,0 +1,208 @@
+//! User settings, stored in state.json as part of `AppState`.
+use std::collections::BTreeMap;
+
+use egui::{Key, KeyboardShortcut, Modifiers};
+use serde::{Deserialize, Serialize};
+
+use crate::dna_widget::NeuronPalette;
+use crate::stats::CostTable;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preferences {
+    pub theme: Theme,
+    /// Rows and columns the cell and gene grids show at least.
+    pub grid_size: u16,
+    /// Earlier versions of a file kept as `<file>.bak1` (newest) and up when saving over it.
+    pub backup_count: u8,
+    /// Creator written into new DNA blocks.
+    pub creator_name: String,
+    pub keybindings: Keybindings,
+    /// Neuron kinds of the number keys in the cell grid.
+    pub neuron_palette: NeuronPalette,
+    /// Energy costs per neuron kind for the upkeep in the statistics.
+    pub upkeep_costs: CostTable,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            theme: Theme::default(),
+            grid_size: 9,
+            backup_count: 0,
+            creator_name: "creator".to_string(),
+            keybindings: Keybindings::default(),
+            neuron_palette: NeuronPalette::default(),
+            upkeep_costs: CostTable::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Theme {
+    /// Follow the operating system.
+    #[default]
+    System,
+    Dark,
+    Light,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::System, Theme::Dark, Theme::Light];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::System => "System",
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+        }
+    }
+
+    pub fn preference(self) -> egui::ThemePreference {
+        match self {
+            Theme::System => egui::ThemePreference::System,
+            Theme::Dark => egui::ThemePreference::Dark,
+            Theme::Light => egui::ThemePreference::Light,
+        }
+    }
+}
+
+/// Shortcuts changed by the user, by action id, written like "Ctrl+Shift+P". Actions without
+/// an entry keep their default shortcut, an empty entry removes it.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Keybindings(pub BTreeMap<String, String>);
+
+impl Keybindings {
+    /// The shortcut of action `id`. Entries that do not parse fall back to the default.
+    pub fn shortcut(
+        &self,
+        id: &str,
+        default: Option<KeyboardShortcut>,
+    ) -> Option<KeyboardShortcut> {
+        match self.0.get(id) {
+            Some(text) if text.is_empty() => None,
+            Some(text) => parse_shortcut(text).or(default),
+            None => default,
+        }
+    }
+
+    /// Binds action `id` to `shortcut`, or unbinds it for `None`. Only differences from the
+    /// default are stored.
+    pub fn set(
+        &mut self,
+        id: &str,
+        shortcut: Option<KeyboardShortcut>,
+        default: Option<KeyboardShortcut>,
+    ) {
+        if shortcut == default {
+            self.0.remove(id);
+        } else {
+            let text = shortcut.map(|shortcut| format_shortcut(&shortcut));
+            self.0.insert(id.to_string(), text.unwrap_or_default());
+        }
+    }
+}
+
+/// Platform independent text of a shortcut; Ctrl stands for Cmd on Mac.
+pub fn format_shortcut(shortcut: &KeyboardShortcut) -> String {
+    let modifiers = shortcut.modifiers;
+    let mut parts = Vec::new();
+    if modifiers.command || modifiers.ctrl {
+        parts.push("Ctrl");
+    }
+    if modifiers.alt {
+        parts.push("Alt");
+    }
+    if modifiers.shift {
+        parts.push("Shift");
+    }
+    parts.push(shortcut.logical_key.name());
+    parts.join("+")
+}
+
+/// Inverse of `format_shortcut`, ignoring case and spaces.
+pub fn parse_shortcut(text: &str) -> Option<KeyboardShortcut> {
+    let mut modifiers = Modifiers::NONE;
+    let mut key = None;
+    for part in text.split('+').map(str::trim) {
+        match part.to_ascii_lowercase().as_str() {
+            "ctrl" | "cmd" | "command" => modifiers |= Modifiers::COMMAND,
+            "alt" => modifiers |= Modifiers::ALT,
+            "shift" => modifiers |= Modifiers::SHIFT,
+            _ if key.is_none() => key = Some(key_from_name(part)?),
+            _ => return None,
+        }
+    }
+    Some(KeyboardShortcut::new(modifiers, key?))
+}
+
+fn key_from_name(name: &str) -> Option<Key> {
+    Key::from_name(name).or_else(|| {
+        let mut chars = name.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Key::from_name(&c.to_ascii_uppercase().to_string()),
+            _ => None,
+        }
+    })
+}
+
+/// The shortcut of a key press, with Ctrl and Cmd both stored as the command modifier.
+pub fn shortcut_of(key: Key, pressed: Modifiers) -> KeyboardShortcut {
+    let mut modifiers = Modifiers::NONE;
+    if pressed.command || pressed.ctrl {
+        modifiers |= Modifiers::COMMAND;
+    }
+    if pressed.alt {
+        modifiers |= Modifiers::ALT;
+    }
+    if pressed.shift {
+        modifiers |= Modifiers::SHIFT;
+    }
+    KeyboardShortcut::new(modifiers, key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shortcut_text_round_trips() {
+        let shortcut = KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::SHIFT, Key::P);
+        assert_eq!(format_shortcut(&shortcut), "Ctrl+Shift+P");
+        assert_eq!(parse_shortcut("Ctrl+Shift+P"), Some(shortcut));
+        assert_eq!(parse_shortcut("shift + ctrl + p"), Some(shortcut));
+        assert_eq!(
+            parse_shortcut("Alt+F5"),
+            Some(KeyboardShortcut::new(Modifiers::ALT, Key::F5))
+        );
+        assert_eq!(parse_shortcut("Ctrl+"), None);
+        assert_eq!(parse_shortcut("Ctrl+P+Q"), None);
+        assert_eq!(
+            shortcut_of(Key::P, Modifiers::CTRL | Modifiers::SHIFT),
+            shortcut
+        );
+    }
+
+    #[test]
+    fn keybindings_store_only_changes() {
+        let default = Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::S));
+        let other = Some(KeyboardShortcut::new(Modifiers::ALT, Key::S));
+        let mut keybindings = Keybindings::default();
+        assert_eq!(keybindings.shortcut("save", default), default);
+
+        keybindings.set("save", other, default);
+        assert_eq!(keybindings.0["save"], "Alt+S");
+        assert_eq!(keybindings.shortcut("save", default), other);
+
+        keybindings.set("save", None, default);
+        assert_eq!(keybindings.shortcut("save", default), None);
+
+        keybindings.set("save", default, default);
+        assert!(keybindings.0.is_empty());
+
+        keybindings
+            .0
+            .insert("save".to_string(), "not a key".to_string());
+        assert_eq!(keybindings.shortcut("save", default), default);
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::BTreeMap;
+
+use egui::{Button, ComboBox, DragValue, Event, Grid, Key, RichText, ScrollArea, Ui};
+
+use crate::actions::global_actions;
+use crate::dnaparser::NeuronType;
+use crate::preferences::{Keybindings, Preferences, Theme, format_shortcut, shortcut_of};
+use crate::stats::CostTable;
+
+/// Window editing the `Preferences`.
+#[derive(Default)]
+pub struct PreferencesWindow {
+    open: bool,
+    /// Action whose new shortcut is waiting for a key press.
+    recording: Option<&'static str>,
+}
+
+impl PreferencesWindow {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn show(&mut self) {
+        self.open = true;
+    }
+
+    /// Whether the next key press is recorded as a shortcut, so it must not run an action.
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    pub fn ui(&mut self, ctx: &egui::Context, preferences: &mut Preferences) {
+        if !self.open {
+            return;
+        }
+
+        let mut open = self.open;
+        egui::Window::new("Preferences")
+            .open(&mut open)
+            .default_width(460.0)
+            .show(ctx, |ui| {
+                Grid::new("preferences_general")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Theme");
+                        ComboBox::from_id_salt("preferences_theme")
+                            .selected_text(preferences.theme.name())
+                            .show_ui(ui, |ui| {
+                                for theme in Theme::ALL {
+                                    ui.selectable_value(
+                                        &mut preferences.theme,
+                                        theme,
+                                        theme.name(),
+                                    );
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Grid size");
+                        ui.add(DragValue::new(&mut preferences.grid_size).range(1..=64))
+                            .on_hover_text(
+                                "Rows and columns the cell and gene grids show at least",
+                            );
+                        ui.end_row();
+
+                        ui.label("Backups");
+                        ui.add(DragValue::new(&mut preferences.backup_count).range(0..=20))
+                            .on_hover_text(
+                                "Earlier versions kept as <file>.bak1 (newest), .bak2, ... when \
+                                 saving over a file. 0 keeps none.",
+                            );
+                        ui.end_row();
+
+                        ui.label("Creator name");
+                        ui.text_edit_singleline(&mut preferences.creator_name)
+                            .on_hover_text("Written into the dna_creator of new DNA blocks");
+                        ui.end_row();
+                    });
+
+                ui.separator();
+                ui.label(RichText::new("Keyboard shortcuts").strong());
+                self.keybindings_ui(ui, &mut preferences.keybindings);
+
+                ui.separator();
+                egui::CollapsingHeader::new("Energy upkeep").show(ui, |ui| {
+                    upkeep_costs_ui(ui, &mut preferences.upkeep_costs);
+                });
+            });
+
+        if !open {
+            self.recording = None;
+        }
+        self.open = open;
+    }
+
+    fn keybindings_ui(&mut self, ui: &mut Ui, keybindings: &mut Keybindings) {
+        let defaults = global_actions(&Keybindings::default());
+        let default_of = |id: &str| {
+            defaults
+                .iter()
+                .find(|entry| entry.id == Some(id))
+                .and_then(|entry| entry.shortcut)
+        };
+
+        if let Some(id) = self.recording {
+            let pressed = ui.input_mut(|i| {
+                let pressed = i.events.iter().find_map(|event| match event {
+                    Event::Key {
+                        key,
+                        pressed: true,
+                        modifiers,
+                        ..
+                    } => Some((*key, *modifiers)),
+                    _ => None,
+                });
+                // The grid and the other shortcuts must not see the recorded key.
+                if pressed.is_some() {
+                    i.events.retain(|event| !matches!(event, Event::Key { .. }));
+                }
+                pressed
+            });
+            match pressed {
+                Some((Key::Escape, modifiers)) if modifiers.is_none() => self.recording = None,
+                Some((key, modifiers)) => {
+                    keybindings.set(id, Some(shortcut_of(key, modifiers)), default_of(id));
+                    self.recording = None;
+                }
+                None => {}
+            }
+        }
+
+        let entries = global_actions(keybindings);
+        let mut users: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+        for entry in &entries {
+            if let Some(shortcut) = entry.shortcut {
+                users
+                    .entry(format_shortcut(&shortcut))
+                    .or_default()
+                    .push(&entry.title);
+            }
+        }
+        for (shortcut, titles) in users.iter().filter(|(_, titles)| titles.len() > 1) {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("⚠ {shortcut} is used by {}", titles.join(", ")),
+            );
+        }
+
+        ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+            Grid::new("preferences_keybindings")
+                .num_columns(4)
+                .striped(true)
+                .show(ui, |ui| {
+                    for entry in &entries {
+                        let Some(id) = entry.id else {
+                            continue;
+                        };
+                        let default = default_of(id);
+                        ui.label(&entry.title);
+                        let text = if self.recording == Some(id) {
+                            "Press keys...".to_string()
+                        } else {
+                            entry
+                                .shortcut
+                                .map(|shortcut| format_shortcut(&shortcut))
+                                .unwrap_or_else(|| "none".to_string())
+                        };
+                        if ui
+                            .button(text)
+                            .on_hover_text("Click, then press the new shortcut. Escape cancels.")
+                            .clicked()
+                        {
+                            self.recording = Some(id);
+                        }
+                        if ui
+                            .add_enabled(entry.shortcut.is_some(), Button::new("Clear"))
+                            .clicked()
+                        {
+                            keybindings.set(id, None, default);
+                        }
+                        if ui
+                            .add_enabled(entry.shortcut != default, Button::new("Reset"))
+                            .clicked()
+                        {
+                            keybindings.set(id, default, default);
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+}
+
+/// Energy per tick of each neuron kind, for the upkeep shown in the statistics. Kinds left at 0
+/// are not stored, and without any cost no upkeep is shown.
+fn upkeep_costs_ui(ui: &mut Ui, costs: &mut CostTable) {
+    ui.horizontal(|ui| {
+        ui.label("Energy per tick of one cell of each kind.");
+        if ui
+            .add_enabled(!costs.0.is_empty(), Button::new("Clear"))
+            .clicked()
+        {
+            costs.0.clear();
+        }
+    });
+    ScrollArea::vertical()
+        .id_salt("preferences_upkeep")
+        .max_height(240.0)
+        .show(ui, |ui| {
+            Grid::new("preferences_upkeep_grid")
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    for neuron_type in NeuronType::all().filter(|kind| kind.to_name() != "unknown")
+                    {
+                        let name = neuron_type.to_name();
+                        let mut cost = costs.cost(neuron_type);
+                        ui.label(name);
+                        if ui
+                            .add(DragValue::new(&mut cost).speed(0.01).range(0.0..=100.0))
+                            .changed()
+                        {
+                            if cost == 0.0 {
+                                costs.0.remove(name);
+                            } else {
+                                costs.0.insert(name.to_string(), cost);
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+}
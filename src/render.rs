@@ -0,0 +1,561 @@
+//! Renders a creature's cells to PNG, SVG or an egui painter.
+//!
+//! `scene` lays out the creature as a list of shapes. The backends only draw those shapes, so
+//! exported images, CLI output and the library thumbnails all look the same.
+use std::fmt::Write as _;
+use std::path::Path;
+
+use egui::{Color32, Pos2, Rect, Vec2, ecolor::Hsva};
+
+use crate::dnaparser::{CreatureDNA, NeuronType};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderOptions {
+    /// Edge length of a cell in pixels.
+    pub cell_size: f32,
+    /// Draw the neuron letter and tag on each cell.
+    pub labels: bool,
+    /// Draw arrows along output tags, green for positive and red for negative weights.
+    pub arrows: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            cell_size: 32.0,
+            labels: false,
+            arrows: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Rect {
+        rect: Rect,
+        fill: Color32,
+        stroke: Color32,
+    },
+    Arrow {
+        from: Pos2,
+        to: Pos2,
+        width: f32,
+        color: Color32,
+    },
+    /// Text centered on `pos`.
+    Text {
+        pos: Pos2,
+        text: String,
+        size: f32,
+        color: Color32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scene {
+    pub size: Vec2,
+    pub background: Color32,
+    pub shapes: Vec<Shape>,
+}
+
+const BACKGROUND: Color32 = Color32::from_rgb(24, 24, 28);
+const CELL_STROKE: Color32 = Color32::from_rgb(10, 10, 12);
+const DEFAULT_SKIN: Color32 = Color32::from_rgb(96, 96, 104);
+
+/// A distinct color per neuron kind.
+pub fn neuron_color(neuron_type: NeuronType) -> Color32 {
+    // Golden ratio steps spread neighbouring kinds over the hue circle.
+    let hue = (f32::from(neuron_type.0.raw()) * 0.618_034).fract();
+    Hsva::new(hue, 0.65, 0.9, 1.0).into()
+}
+
+pub fn skin_color(dna: &CreatureDNA) -> Option<Color32> {
+    let [r, g, b] = dna.creature.skin_color()?.to_rgb8();
+    Some(Color32::from_rgb(r, g, b))
+}
+
+pub fn scene(dna: &CreatureDNA, options: &RenderOptions) -> Scene {
+    let cells = &dna.cells;
+    let min_x = cells.iter().map(|cell| cell.index.x).min().unwrap_or(0);
+    let min_y = cells.iter().map(|cell| cell.index.y).min().unwrap_or(0);
+    let max_x = cells.iter().map(|cell| cell.index.x).max().unwrap_or(0);
+    let max_y = cells.iter().map(|cell| cell.index.y).max().unwrap_or(0);
+
+    let size = options.cell_size;
+    let margin = size * 0.25;
+    let cell_rect = |x: u16, y: u16| {
+        Rect::from_min_size(
+            Pos2::new(
+                margin + f32::from(x - min_x) * size,
+                margin + f32::from(y - min_y) * size,
+            ),
+            Vec2::splat(size),
+        )
+    };
+
+    let skin = skin_color(dna).unwrap_or(DEFAULT_SKIN);
+    let mut shapes = Vec::new();
+    for cell in cells.iter() {
+        let rect = cell_rect(cell.index.x, cell.index.y);
+        shapes.push(Shape::Rect {
+            rect,
+            fill: skin,
+            stroke: CELL_STROKE,
+        });
+        shapes.push(Shape::Rect {
+            rect: rect.shrink(size * 0.15),
+            fill: neuron_color(cell.decoded.neuron_type),
+            stroke: CELL_STROKE,
+        });
+        if options.labels {
+            shapes.push(Shape::Text {
+                pos: rect.center(),
+                text: format!(
+                    "{}{}",
+                    cell.decoded.neuron_type.to_char(),
+                    cell.decoded.tag.to_char()
+                ),
+                size: size * 0.35,
+                color: Color32::BLACK,
+            });
+        }
+    }
+
+    if options.arrows {
+        for source in cells.iter() {
+            let from = cell_rect(source.index.x, source.index.y).center();
+            for output in &source.decoded.output_tags {
+                let weight = output.weight.as_weight();
+                let alpha = (weight.abs() / 2.5 * 200.0 + 55.0) as u8;
+                let color = if weight >= 0.0 {
+                    Color32::from_rgba_unmultiplied(60, 220, 90, alpha)
+                } else {
+                    Color32::from_rgba_unmultiplied(230, 60, 60, alpha)
+                };
+                for target in cells.iter() {
+                    if target.index == source.index || target.decoded.tag.0 != output.tag {
+                        continue;
+                    }
+                    let to = cell_rect(target.index.x, target.index.y).center();
+                    let shorten = (to - from).normalized() * size * 0.3;
+                    shapes.push(Shape::Arrow {
+                        from: from + shorten,
+                        to: to - shorten,
+                        width: (size * 0.05).max(1.0),
+                        color,
+                    });
+                }
+            }
+        }
+    }
+
+    let scene_size = if cells.is_empty() {
+        Vec2::splat(2.0 * margin)
+    } else {
+        Vec2::new(
+            f32::from(max_x - min_x + 1) * size + 2.0 * margin,
+            f32::from(max_y - min_y + 1) * size + 2.0 * margin,
+        )
+    };
+    Scene {
+        size: scene_size,
+        background: BACKGROUND,
+        shapes,
+    }
+}
+
+fn arrow_head(from: Pos2, to: Pos2, width: f32) -> [Pos2; 3] {
+    let dir = (to - from).normalized();
+    let normal = dir.rot90();
+    let length = width * 4.0;
+    let back = to - dir * length;
+    [
+        to,
+        back + normal * length * 0.5,
+        back - normal * length * 0.5,
+    ]
+}
+
+pub fn to_svg(scene: &Scene) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = scene.size.x,
+        h = scene.size.y
+    );
+    let _ = writeln!(
+        out,
+        r#"<rect width="100%" height="100%" {}/>"#,
+        svg_fill(scene.background)
+    );
+    for shape in &scene.shapes {
+        match shape {
+            Shape::Rect { rect, fill, stroke } => {
+                let _ = writeln!(
+                    out,
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" {} stroke="{}"/>"#,
+                    rect.min.x,
+                    rect.min.y,
+                    rect.width(),
+                    rect.height(),
+                    svg_fill(*fill),
+                    svg_color(*stroke)
+                );
+            }
+            Shape::Arrow {
+                from,
+                to,
+                width,
+                color,
+            } => {
+                let [tip, left, right] = arrow_head(*from, *to, *width);
+                let _ = writeln!(
+                    out,
+                    r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-opacity="{}" stroke-width="{}"/>"#,
+                    from.x,
+                    from.y,
+                    to.x,
+                    to.y,
+                    svg_color(*color),
+                    svg_opacity(*color),
+                    width
+                );
+                let _ = writeln!(
+                    out,
+                    r#"<polygon points="{},{} {},{} {},{}" {}/>"#,
+                    tip.x,
+                    tip.y,
+                    left.x,
+                    left.y,
+                    right.x,
+                    right.y,
+                    svg_fill(*color)
+                );
+            }
+            Shape::Text {
+                pos,
+                text,
+                size,
+                color,
+            } => {
+                let escaped = text
+                    .replace('&', "&amp;")
+                    .replace('<', "&lt;")
+                    .replace('>', "&gt;");
+                let _ = writeln!(
+                    out,
+                    r#"<text x="{}" y="{}" font-family="sans-serif" font-size="{}" text-anchor="middle" dominant-baseline="central" {}>{}</text>"#,
+                    pos.x,
+                    pos.y,
+                    size,
+                    svg_fill(*color),
+                    escaped
+                );
+            }
+        }
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+fn svg_color(color: Color32) -> String {
+    let [r, g, b, _] = color.to_srgba_unmultiplied();
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+fn svg_opacity(color: Color32) -> f32 {
+    f32::from(color.a()) / 255.0
+}
+
+fn svg_fill(color: Color32) -> String {
+    format!(
+        r#"fill="{}" fill-opacity="{}""#,
+        svg_color(color),
+        svg_opacity(color)
+    )
+}
+
+pub fn to_png(scene: &Scene) -> Result<Vec<u8>, String> {
+    use tiny_skia::{FillRule, Paint, PathBuilder, Pixmap, Stroke, Transform};
+
+    let width = scene.size.x.ceil().max(1.0) as u32;
+    let height = scene.size.y.ceil().max(1.0) as u32;
+    let mut pixmap = Pixmap::new(width, height).ok_or("image too large")?;
+    pixmap.fill(skia_color(scene.background));
+
+    let paint = |color: Color32| {
+        let mut paint = Paint::default();
+        paint.set_color(skia_color(color));
+        paint.anti_alias = true;
+        paint
+    };
+    let font = LabelFont::new();
+
+    for shape in &scene.shapes {
+        match shape {
+            Shape::Rect { rect, fill, stroke } => {
+                let Some(skia_rect) =
+                    tiny_skia::Rect::from_xywh(rect.min.x, rect.min.y, rect.width(), rect.height())
+                else {
+                    continue;
+                };
+                let path = PathBuilder::from_rect(skia_rect);
+                pixmap.fill_path(
+                    &path,
+                    &paint(*fill),
+                    FillRule::Winding,
+                    Transform::identity(),
+                    None,
+                );
+                pixmap.stroke_path(
+                    &path,
+                    &paint(*stroke),
+                    &Stroke::default(),
+                    Transform::identity(),
+                    None,
+                );
+            }
+            Shape::Arrow {
+                from,
+                to,
+                width,
+                color,
+            } => {
+                let mut line = PathBuilder::new();
+                line.move_to(from.x, from.y);
+                line.line_to(to.x, to.y);
+                let stroke = Stroke {
+                    width: *width,
+                    ..Stroke::default()
+                };
+                if let Some(path) = line.finish() {
+                    pixmap.stroke_path(&path, &paint(*color), &stroke, Transform::identity(), None);
+                }
+
+                let [tip, left, right] = arrow_head(*from, *to, *width);
+                let mut head = PathBuilder::new();
+                head.move_to(tip.x, tip.y);
+                head.line_to(left.x, left.y);
+                head.line_to(right.x, right.y);
+                head.close();
+                if let Some(path) = head.finish() {
+                    pixmap.fill_path(
+                        &path,
+                        &paint(*color),
+                        FillRule::Winding,
+                        Transform::identity(),
+                        None,
+                    );
+                }
+            }
+            Shape::Text {
+                pos,
+                text,
+                size,
+                color,
+            } => {
+                if let Some(font) = font.as_ref() {
+                    font.draw(&mut pixmap, *pos, text, *size, *color);
+                }
+            }
+        }
+    }
+
+    pixmap.encode_png().map_err(|err| err.to_string())
+}
+
+fn skia_color(color: Color32) -> tiny_skia::Color {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    tiny_skia::Color::from_rgba8(r, g, b, a)
+}
+
+/// The proportional font egui ships with, used to draw labels into PNGs.
+struct LabelFont {
+    data: std::sync::Arc<egui::FontData>,
+}
+
+impl LabelFont {
+    fn new() -> Option<Self> {
+        let fonts = egui::FontDefinitions::default();
+        let name = fonts
+            .families
+            .get(&egui::FontFamily::Proportional)?
+            .first()?;
+        let data = fonts.font_data.get(name)?.clone();
+        Some(Self { data })
+    }
+
+    fn draw(
+        &self,
+        pixmap: &mut tiny_skia::Pixmap,
+        center: Pos2,
+        text: &str,
+        size: f32,
+        color: Color32,
+    ) {
+        use ab_glyph::{Font, FontRef, PxScale, ScaleFont, point};
+
+        let Ok(font) = FontRef::try_from_slice_and_index(&self.data.font, self.data.index) else {
+            return;
+        };
+        let scaled = font.as_scaled(PxScale::from(size));
+        let width: f32 = text
+            .chars()
+            .map(|c| scaled.h_advance(scaled.glyph_id(c)))
+            .sum();
+        let baseline = center.y + (scaled.ascent() + scaled.descent()) / 2.0;
+
+        let [r, g, b, a] = color.to_srgba_unmultiplied();
+        let pixmap_width = pixmap.width() as i32;
+        let pixmap_height = pixmap.height() as i32;
+        let pixels = pixmap.pixels_mut();
+
+        let mut x = center.x - width / 2.0;
+        for c in text.chars() {
+            let mut glyph = scaled.scaled_glyph(c);
+            glyph.position = point(x, baseline);
+            x += scaled.h_advance(glyph.id);
+            let Some(outlined) = font.outline_glyph(glyph) else {
+                continue;
+            };
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, coverage| {
+                let px = bounds.min.x as i32 + gx as i32;
+                let py = bounds.min.y as i32 + gy as i32;
+                if px < 0 || py < 0 || px >= pixmap_width || py >= pixmap_height {
+                    return;
+                }
+                let pixel = &mut pixels[(py * pixmap_width + px) as usize];
+                let alpha = coverage.clamp(0.0, 1.0) * f32::from(a) / 255.0;
+                let blend = |src: u8, dst: u8| {
+                    (f32::from(src) * alpha + f32::from(dst) * (1.0 - alpha)).round() as u8
+                };
+                let blended = tiny_skia::PremultipliedColorU8::from_rgba(
+                    blend(r, pixel.red()),
+                    blend(g, pixel.green()),
+                    blend(b, pixel.blue()),
+                    blend(255, pixel.alpha()),
+                );
+                if let Some(blended) = blended {
+                    *pixel = blended;
+                }
+            });
+        }
+    }
+}
+
+/// Renders `dna` to `path`, as SVG if the extension is `svg` and as PNG otherwise.
+pub fn write_image(path: &Path, dna: &CreatureDNA, options: &RenderOptions) -> Result<(), String> {
+    let scene = scene(dna, options);
+    let is_svg = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+    let content = if is_svg {
+        to_svg(&scene).into_bytes()
+    } else {
+        to_png(&scene)?
+    };
+    std::fs::write(path, content)
+        .map_err(|err| format!("failed to write '{}': {err}", path.display()))
+}
+
+/// Draws `scene` scaled to fit into `rect`.
+pub fn paint(painter: &egui::Painter, rect: Rect, scene: &Scene) {
+    let scale = (rect.width() / scene.size.x).min(rect.height() / scene.size.y);
+    let offset = rect.center() - scene.size * scale / 2.0;
+    let map = |pos: Pos2| offset + pos.to_vec2() * scale;
+
+    painter.rect_filled(rect, 2.0, scene.background);
+    for shape in &scene.shapes {
+        match shape {
+            Shape::Rect { rect, fill, stroke } => {
+                let mapped = Rect::from_min_max(map(rect.min), map(rect.max));
+                painter.rect_filled(mapped, 0.0, *fill);
+                if scale * rect.width() > 8.0 {
+                    painter.rect_stroke(
+                        mapped,
+                        0.0,
+                        egui::Stroke::new(1.0, *stroke),
+                        egui::StrokeKind::Inside,
+                    );
+                }
+            }
+            Shape::Arrow {
+                from,
+                to,
+                width,
+                color,
+            } => {
+                painter.arrow(
+                    map(*from),
+                    map(*to) - map(*from),
+                    egui::Stroke::new(width * scale, *color),
+                );
+            }
+            Shape::Text {
+                pos,
+                text,
+                size,
+                color,
+            } => {
+                // Too small to read in thumbnails.
+                if size * scale >= 6.0 {
+                    painter.text(
+                        map(*pos),
+                        egui::Align2::CENTER_CENTER,
+                        text,
+                        egui::FontId::proportional(size * scale),
+                        *color,
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn latch() -> CreatureDNA {
+        crate::read_creature("data/latch_mod.txt").expect("failed to read data/latch_mod.txt")
+    }
+
+    #[test]
+    fn svg_has_a_cell_per_neuron() {
+        let dna = latch();
+        let options = RenderOptions {
+            labels: true,
+            arrows: true,
+            ..RenderOptions::default()
+        };
+        let svg = to_svg(&scene(&dna, &options));
+        assert!(svg.starts_with("<svg"));
+        // Background plus skin and neuron square per cell.
+        assert_eq!(svg.matches("<rect").count(), 1 + 2 * dna.cells.len());
+        assert_eq!(svg.matches("<text").count(), dna.cells.len());
+        assert!(svg.contains("<polygon"));
+    }
+
+    #[test]
+    fn png_has_scene_size() {
+        let dna = latch();
+        let options = RenderOptions {
+            labels: true,
+            ..RenderOptions::default()
+        };
+        let scene = scene(&dna, &options);
+        let png = to_png(&scene).expect("failed to encode png");
+        let decoded = tiny_skia::Pixmap::decode_png(&png).expect("failed to decode png");
+        assert_eq!(decoded.width(), scene.size.x.ceil() as u32);
+        assert_eq!(decoded.height(), scene.size.y.ceil() as u32);
+    }
+
+    #[test]
+    fn skin_color_is_parsed_from_letters() {
+        let dna = latch();
+        // "aHa": a = 26, H = 7
+        assert_eq!(skin_color(&dna), Some(Color32::from_rgb(105, 28, 105)));
+    }
+}
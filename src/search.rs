@@ -0,0 +1,331 @@
+//! Finding cells and genes by their content and replacing values in all matches, across the
+//! cells and every DNA layer.
+use crate::dnaparser::{CreatureDNA, DecodedGeneInfo, GeneTag, NeuronType, PropertyValue};
+use crate::merge::RecordLocation;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Criterion {
+    NeuronKind(NeuronType),
+    Tag(PropertyValue),
+    /// Property `index` (0..8) has `value`.
+    Property {
+        index: usize,
+        value: PropertyValue,
+    },
+    /// One of the output tags points at this tag.
+    OutputTarget(PropertyValue),
+    /// Substring of the encoded gene, like `*e` or `J!`.
+    Encoded(String),
+}
+
+impl Criterion {
+    pub fn matches(&self, info: &DecodedGeneInfo) -> bool {
+        match self {
+            Criterion::NeuronKind(neuron_type) => info.neuron_type == *neuron_type,
+            Criterion::Tag(tag) => info.tag.0 == *tag,
+            Criterion::Property { index, value } => info
+                .properties
+                .get(*index)
+                .is_some_and(|property| property.0 == *value),
+            Criterion::OutputTarget(tag) => {
+                info.output_tags.iter().any(|output| output.tag == *tag)
+            }
+            Criterion::Encoded(text) => {
+                !text.is_empty()
+                    && info
+                        .encode()
+                        .is_ok_and(|encoded| encoded.contains(text.as_str()))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Replacement {
+    NeuronKind(NeuronType),
+    Tag(PropertyValue),
+    Property {
+        index: usize,
+        value: PropertyValue,
+    },
+    /// Output tags pointing at `from` point at `to` instead.
+    OutputTarget {
+        from: PropertyValue,
+        to: PropertyValue,
+    },
+    /// Text replacement in the encoded gene. Records whose result does not decode are kept.
+    Encoded {
+        from: String,
+        to: String,
+    },
+}
+
+impl Replacement {
+    /// Returns whether `info` changed.
+    pub fn apply(&self, info: &mut DecodedGeneInfo) -> bool {
+        let before = info.clone();
+        match self {
+            Replacement::NeuronKind(neuron_type) => info.neuron_type = *neuron_type,
+            Replacement::Tag(tag) => info.tag.0 = *tag,
+            Replacement::Property { index, value } => {
+                if let Some(property) = info.properties.get_mut(*index) {
+                    property.0 = *value;
+                }
+            }
+            Replacement::OutputTarget { from, to } => {
+                for output in info.output_tags.iter_mut() {
+                    if output.tag == *from {
+                        output.tag = *to;
+                    }
+                }
+            }
+            Replacement::Encoded { from, to } => {
+                if !from.is_empty()
+                    && let Ok(encoded) = info.encode()
+                    && let Ok(replaced) =
+                        DecodedGeneInfo::decode(&encoded.replace(from.as_str(), to))
+                {
+                    *info = replaced;
+                }
+            }
+        }
+        *info != before
+    }
+}
+
+/// Locations of all cells and genes matching `criterion`, cells first.
+pub fn find(dna: &CreatureDNA, criterion: &Criterion) -> Vec<RecordLocation> {
+    let mut matches: Vec<RecordLocation> = dna
+        .cells
+        .iter()
+        .filter(|cell| criterion.matches(&cell.decoded))
+        .map(|cell| RecordLocation::Cell(cell.index))
+        .collect();
+    for (dna_idx, block) in dna.dna.iter().enumerate() {
+        for layer in block.genes.iter() {
+            for gene in layer.genes.iter() {
+                if criterion.matches(&gene.decoded) {
+                    matches.push(RecordLocation::Gene {
+                        dna_idx,
+                        z_level: layer.z_level,
+                        index: gene.index,
+                    });
+                }
+            }
+        }
+    }
+    matches
+}
+
+/// Applies `replacement` to every record matching `criterion`. Returns the number of records
+/// that changed.
+pub fn replace(dna: &mut CreatureDNA, criterion: &Criterion, replacement: &Replacement) -> usize {
+    let cells = dna.cells.iter_mut().map(|cell| &mut cell.decoded);
+    let genes = dna
+        .dna
+        .iter_mut()
+        .flat_map(|block| block.genes.iter_mut())
+        .flat_map(|layer| layer.genes.iter_mut())
+        .map(|gene| &mut gene.decoded);
+    cells
+        .chain(genes)
+        .filter(|info| criterion.matches(info))
+        .map(|info| replacement.apply(info))
+        .filter(|changed| *changed)
+        .count()
+}
+
+/// Records a tag rename touches, shown before it is applied.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TagRename {
+    /// Records carrying the old tag.
+    pub carriers: Vec<RecordLocation>,
+    /// Records with output tags addressing the old tag.
+    pub references: Vec<RecordLocation>,
+    /// Records already carrying the new tag. Renaming would connect them to the references.
+    pub collisions: Vec<RecordLocation>,
+    /// Records with output tags already addressing the new tag. Renaming would connect them to
+    /// the carriers.
+    pub dangling: Vec<RecordLocation>,
+}
+
+impl TagRename {
+    pub fn preview(dna: &CreatureDNA, from: PropertyValue, to: PropertyValue) -> Self {
+        Self {
+            carriers: find(dna, &Criterion::Tag(from)),
+            references: find(dna, &Criterion::OutputTarget(from)),
+            collisions: if from == to {
+                Vec::new()
+            } else {
+                find(dna, &Criterion::Tag(to))
+            },
+            dangling: if from == to {
+                Vec::new()
+            } else {
+                find(dna, &Criterion::OutputTarget(to))
+            },
+        }
+    }
+
+    /// Whether renaming would connect records that are not connected now.
+    pub fn merges_connections(&self) -> bool {
+        !self.collisions.is_empty() || !self.dangling.is_empty()
+    }
+}
+
+/// Renames tag `from` to `to` in the records carrying it and in every output tag addressing it,
+/// in the cells and all DNA layers. Refuses if another record already carries `to` or output
+/// tags already address it.
+pub fn rename_tag(
+    dna: &mut CreatureDNA,
+    from: PropertyValue,
+    to: PropertyValue,
+) -> Result<TagRename, String> {
+    let rename = TagRename::preview(dna, from, to);
+    if !rename.collisions.is_empty() {
+        return Err(format!(
+            "tag '{}' is already used by {} record(s)",
+            GeneTag(to).to_char(),
+            rename.collisions.len()
+        ));
+    }
+    if !rename.dangling.is_empty() {
+        return Err(format!(
+            "tag '{}' is already addressed by output tags of {} record(s)",
+            GeneTag(to).to_char(),
+            rename.dangling.len()
+        ));
+    }
+    replace(
+        dna,
+        &Criterion::OutputTarget(from),
+        &Replacement::OutputTarget { from, to },
+    );
+    replace(dna, &Criterion::Tag(from), &Replacement::Tag(to));
+    Ok(rename)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dnaparser::OutputTag;
+
+    fn letter(c: char) -> PropertyValue {
+        PropertyValue::from_char(c).expect("valid letter")
+    }
+
+    #[test]
+    fn finds_and_replaces_output_targets() {
+        let mut dna = crate::read_creature("data/e5.txt").expect("failed to read data/e5.txt");
+        let target = dna
+            .cells
+            .iter()
+            .find_map(|cell| cell.decoded.output_tags.first())
+            .expect("some cell has an output tag")
+            .tag;
+        let other = PropertyValue::new((target.raw() + 1) % 64).expect("valid value");
+        let found = find(&dna, &Criterion::OutputTarget(target));
+        let found_other = find(&dna, &Criterion::OutputTarget(other));
+        assert!(!found.is_empty());
+
+        let replacement = Replacement::OutputTarget {
+            from: target,
+            to: other,
+        };
+        let changed = replace(&mut dna, &Criterion::OutputTarget(target), &replacement);
+        assert_eq!(changed, found.len());
+        assert!(find(&dna, &Criterion::OutputTarget(target)).is_empty());
+        let merged = find(&dna, &Criterion::OutputTarget(other));
+        assert!(
+            found
+                .iter()
+                .chain(&found_other)
+                .all(|location| merged.contains(location))
+        );
+        assert!(merged.len() <= found.len() + found_other.len());
+    }
+
+    #[test]
+    fn replaces_properties_of_one_kind_and_encoded_text() {
+        let mut dna = crate::read_creature("data/e5.txt").expect("failed to read data/e5.txt");
+        let kind = dna.cells[0].decoded.neuron_type;
+        let of_kind = find(&dna, &Criterion::NeuronKind(kind));
+        let set = Replacement::Property {
+            index: 2,
+            value: letter('!'),
+        };
+        replace(&mut dna, &Criterion::NeuronKind(kind), &set);
+        let criterion = Criterion::Property {
+            index: 2,
+            value: letter('!'),
+        };
+        assert!(
+            of_kind
+                .iter()
+                .all(|location| find(&dna, &criterion).contains(location))
+        );
+
+        let neuron = format!("*{}", kind.to_char());
+        assert_eq!(find(&dna, &Criterion::Encoded(neuron.clone())), of_kind);
+        let broken = Replacement::Encoded {
+            from: neuron.clone(),
+            to: "*#".to_string(),
+        };
+        assert_eq!(
+            replace(&mut dna, &Criterion::Encoded(neuron.clone()), &broken),
+            0
+        );
+    }
+
+    #[test]
+    fn renames_tags_with_their_references() {
+        let mut dna = crate::read_creature("data/e5.txt").expect("failed to read data/e5.txt");
+        let from = dna.cells[0].decoded.tag.0;
+        let taken = PropertyValue::new((from.raw() + 1) % 64).expect("tag in range");
+        dna.cells[1].decoded.tag.0 = taken;
+        let free = (0..64)
+            .filter_map(PropertyValue::new)
+            .find(|tag| find(&dna, &Criterion::Tag(*tag)).is_empty())
+            .expect("some tag is not carried");
+
+        let before = dna.clone();
+        assert!(!TagRename::preview(&dna, from, taken).collisions.is_empty());
+        assert!(rename_tag(&mut dna, from, taken).is_err());
+        assert_eq!(dna, before);
+
+        // Output tags addressing the free tag would silently connect to the renamed records.
+        let mut dangling = dna.clone();
+        dangling.cells[1].decoded.output_tags.push(OutputTag {
+            tag: free,
+            weight: letter('g'),
+        });
+        let preview = TagRename::preview(&dangling, from, free);
+        assert!(preview.collisions.is_empty());
+        assert!(preview.merges_connections());
+        let before = dangling.clone();
+        assert!(rename_tag(&mut dangling, from, free).is_err());
+        assert_eq!(dangling, before);
+
+        let unaddressed = |output: &OutputTag| output.tag != free;
+        for cell in dna.cells.iter_mut() {
+            cell.decoded.output_tags.retain(unaddressed);
+        }
+        for block in dna.dna.iter_mut() {
+            for layer in block.genes.iter_mut() {
+                for gene in layer.genes.iter_mut() {
+                    gene.decoded.output_tags.retain(unaddressed);
+                }
+            }
+        }
+        let preview = TagRename::preview(&dna, from, free);
+        assert!(!preview.merges_connections());
+        assert_eq!(rename_tag(&mut dna, from, free), Ok(preview.clone()));
+        assert!(find(&dna, &Criterion::Tag(from)).is_empty());
+        assert!(find(&dna, &Criterion::OutputTarget(from)).is_empty());
+        assert_eq!(find(&dna, &Criterion::Tag(free)), preview.carriers);
+        assert_eq!(
+            find(&dna, &Criterion::OutputTarget(free)),
+            preview.references
+        );
+    }
+}
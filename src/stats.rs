@@ -0,0 +1,361 @@
+//! Statistics of a creature: what its cells are, how they are wired, and what they cost.
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dnaparser::{CreatureDNA, GridIndex2, NeuronType};
+use crate::pdf_infos::{NeuronCategory, neuron_category};
+
+/// Energy a cell of each neuron kind costs per tick, by kind name like "eye". Kinds that are
+/// not listed cost nothing; an empty table means no costs are configured.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct CostTable(pub BTreeMap<String, f32>);
+
+impl CostTable {
+    /// Reads a JSON object of kind names and costs, like `{"eye": 0.2, "fin": 0.5}`.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let table: Self =
+            serde_json::from_str(json).map_err(|err| format!("invalid cost table: {err}"))?;
+        if let Some(name) = table
+            .0
+            .keys()
+            .find(|name| NeuronType::all().all(|kind| kind.to_name() != name.as_str()))
+        {
+            return Err(format!("unknown neuron kind '{name}' in cost table"));
+        }
+        Ok(table)
+    }
+
+    pub fn cost(&self, neuron_type: NeuronType) -> f32 {
+        self.0.get(neuron_type.to_name()).copied().unwrap_or(0.0)
+    }
+}
+
+/// Number of genes in a layer of a DNA block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerGenes {
+    pub dna_idx: usize,
+    pub z_level: u16,
+    pub genes: usize,
+}
+
+/// Connections of one cell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fan {
+    pub index: GridIndex2,
+    pub neuron_type: NeuronType,
+    /// Output tags of all cells addressing this cell's tag.
+    pub fan_in: usize,
+    /// Output tags of this cell.
+    pub fan_out: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreatureStats {
+    pub cells: usize,
+    /// Cells per neuron kind, most common first.
+    pub kinds: Vec<(NeuronType, usize)>,
+    /// Cells per category, in the order of `NeuronCategory::ALL`.
+    pub categories: Vec<(NeuronCategory, usize)>,
+    pub genes: usize,
+    pub layers: Vec<LayerGenes>,
+    /// Output tags of the cells.
+    pub connections: usize,
+    /// Output tags addressing a tag no cell carries.
+    pub dangling: usize,
+    /// Sum of the decoded output tag weights.
+    pub weight_sum: f32,
+    /// Sum of the absolute decoded output tag weights.
+    pub abs_weight_sum: f32,
+    /// Cells in grid order.
+    pub fans: Vec<Fan>,
+    /// Smallest and largest cell position.
+    pub bounding_box: Option<(GridIndex2, GridIndex2)>,
+    /// Share of cells whose mirror image across the middle column of the bounding box is a cell
+    /// of the same kind: 1 for a left-right symmetric creature.
+    pub symmetry: f32,
+    /// Summed cost of the cells, if a cost table is configured.
+    pub upkeep: Option<f32>,
+}
+
+impl CreatureStats {
+    pub fn new(dna: &CreatureDNA, costs: &CostTable) -> Self {
+        let mut kind_counts = [0; 64];
+        for cell in dna.cells.iter() {
+            kind_counts[usize::from(cell.decoded.neuron_type.0.raw()) % 64] += 1;
+        }
+        let mut kinds: Vec<(NeuronType, usize)> = NeuronType::all()
+            .map(|kind| (kind, kind_counts[usize::from(kind.0.raw()) % 64]))
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        kinds.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.to_name().cmp(b.0.to_name())));
+        let categories = NeuronCategory::ALL
+            .into_iter()
+            .map(|category| {
+                let count = kinds
+                    .iter()
+                    .filter(|(kind, _)| neuron_category(kind_char(*kind)) == category)
+                    .map(|(_, count)| count)
+                    .sum();
+                (category, count)
+            })
+            .collect();
+
+        let layers: Vec<LayerGenes> = dna
+            .dna
+            .iter()
+            .enumerate()
+            .flat_map(|(dna_idx, block)| {
+                block.genes.iter().map(move |layer| LayerGenes {
+                    dna_idx,
+                    z_level: layer.z_level,
+                    genes: layer.genes.len(),
+                })
+            })
+            .collect();
+
+        let mut fan_in: HashMap<u8, usize> = HashMap::new();
+        let mut weight_sum = 0.0;
+        let mut abs_weight_sum = 0.0;
+        for output in dna.cells.iter().flat_map(|cell| &cell.decoded.output_tags) {
+            *fan_in.entry(output.tag.raw()).or_default() += 1;
+            weight_sum += output.weight.as_weight();
+            abs_weight_sum += output.weight.as_weight().abs();
+        }
+        let carried: HashSet<u8> = dna
+            .cells
+            .iter()
+            .map(|cell| cell.decoded.tag.0.raw())
+            .collect();
+        let dangling = fan_in
+            .iter()
+            .filter(|(tag, _)| !carried.contains(tag))
+            .map(|(_, count)| count)
+            .sum();
+
+        let mut fans: Vec<Fan> = dna
+            .cells
+            .iter()
+            .map(|cell| Fan {
+                index: cell.index,
+                neuron_type: cell.decoded.neuron_type,
+                fan_in: fan_in.get(&cell.decoded.tag.0.raw()).copied().unwrap_or(0),
+                fan_out: cell.decoded.output_tags.len(),
+            })
+            .collect();
+        fans.sort_by_key(|fan| (fan.index.y, fan.index.x));
+
+        Self {
+            cells: dna.cells.len(),
+            kinds,
+            categories,
+            genes: layers.iter().map(|layer| layer.genes).sum(),
+            layers,
+            connections: fan_in.values().sum(),
+            dangling,
+            weight_sum,
+            abs_weight_sum,
+            fans,
+            bounding_box: bounding_box(dna),
+            symmetry: symmetry(dna),
+            upkeep: (!costs.0.is_empty()).then(|| {
+                dna.cells
+                    .iter()
+                    .map(|cell| costs.cost(cell.decoded.neuron_type))
+                    .sum()
+            }),
+        }
+    }
+
+    pub fn average_weight(&self) -> Option<f32> {
+        (self.connections > 0).then(|| self.weight_sum / self.connections as f32)
+    }
+
+    /// Plain text version, as printed by the `info` command.
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "cells: {}", self.cells);
+        for (category, count) in &self.categories {
+            let _ = writeln!(out, "  {}: {count}", category.name());
+        }
+        let _ = writeln!(out, "neuron kinds:");
+        for (kind, count) in &self.kinds {
+            let _ = writeln!(out, "  {}: {count}", kind.to_name());
+        }
+        let _ = writeln!(out, "genes: {}", self.genes);
+        for layer in &self.layers {
+            let _ = writeln!(
+                out,
+                "  dna {} layer {}: {}",
+                layer.dna_idx, layer.z_level, layer.genes
+            );
+        }
+        let _ = writeln!(
+            out,
+            "connections: {} ({} dangling)",
+            self.connections, self.dangling
+        );
+        let _ = writeln!(
+            out,
+            "weights: sum {:.3}, absolute sum {:.3}, average {}",
+            self.weight_sum,
+            self.abs_weight_sum,
+            self.average_weight()
+                .map_or("-".to_string(), |average| format!("{average:.3}"))
+        );
+        let _ = writeln!(out, "fan-in/fan-out:");
+        for fan in &self.fans {
+            let _ = writeln!(
+                out,
+                "  [{}][{}] {}: {}/{}",
+                fan.index.x,
+                fan.index.y,
+                fan.neuron_type.to_name(),
+                fan.fan_in,
+                fan.fan_out
+            );
+        }
+        match self.bounding_box {
+            Some((min, max)) => {
+                let _ = writeln!(
+                    out,
+                    "bounding box: [{}][{}] to [{}][{}] ({}x{})",
+                    min.x,
+                    min.y,
+                    max.x,
+                    max.y,
+                    max.x - min.x + 1,
+                    max.y - min.y + 1
+                );
+            }
+            None => {
+                let _ = writeln!(out, "bounding box: -");
+            }
+        }
+        let _ = writeln!(out, "symmetry: {:.0}%", self.symmetry * 100.0);
+        if let Some(upkeep) = self.upkeep {
+            let _ = writeln!(out, "energy upkeep: {upkeep:.3}");
+        }
+        out
+    }
+}
+
+fn kind_char(neuron_type: NeuronType) -> char {
+    neuron_type.0.to_char().unwrap_or('?')
+}
+
+fn bounding_box(dna: &CreatureDNA) -> Option<(GridIndex2, GridIndex2)> {
+    let xs = || dna.cells.iter().map(|cell| cell.index.x);
+    let ys = || dna.cells.iter().map(|cell| cell.index.y);
+    Some((
+        GridIndex2 {
+            x: xs().min()?,
+            y: ys().min()?,
+        },
+        GridIndex2 {
+            x: xs().max()?,
+            y: ys().max()?,
+        },
+    ))
+}
+
+fn symmetry(dna: &CreatureDNA) -> f32 {
+    let Some((min, max)) = bounding_box(dna) else {
+        return 0.0;
+    };
+    let kinds: HashMap<GridIndex2, NeuronType> = dna
+        .cells
+        .iter()
+        .map(|cell| (cell.index, cell.decoded.neuron_type))
+        .collect();
+    let mirrored = kinds
+        .iter()
+        .filter(|(index, kind)| {
+            let mirrored = u32::from(min.x) + u32::from(max.x) - u32::from(index.x);
+            let Ok(x) = u16::try_from(mirrored) else {
+                return false;
+            };
+            kinds.get(&GridIndex2 { x, y: index.y }) == Some(kind)
+        })
+        .count();
+    mirrored as f32 / kinds.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dnaparser::{Cells, NeuronProperties, PropertyValue};
+
+    #[test]
+    fn counts_and_connections() {
+        let dna = crate::read_creature("data/e5.txt").expect("failed to read data/e5.txt");
+        let stats = CreatureStats::new(&dna, &CostTable::default());
+        assert_eq!(stats.cells, dna.cells.len());
+        assert_eq!(
+            stats.kinds.iter().map(|(_, n)| n).sum::<usize>(),
+            stats.cells
+        );
+        assert_eq!(
+            stats.categories.iter().map(|(_, n)| n).sum::<usize>(),
+            stats.cells
+        );
+        assert!(stats.kinds.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        assert_eq!(
+            stats.connections,
+            stats.fans.iter().map(|fan| fan.fan_out).sum::<usize>()
+        );
+        let outputs = || dna.cells.iter().flat_map(|cell| &cell.decoded.output_tags);
+        for fan in &stats.fans {
+            let cell = dna
+                .cells
+                .get_cell_at(fan.index.x, fan.index.y)
+                .expect("a cell");
+            let addressing = outputs().filter(|output| output.tag == cell.tag.0).count();
+            assert_eq!(fan.fan_in, addressing);
+        }
+        assert_eq!(
+            stats.genes,
+            dna.dna
+                .iter()
+                .map(|block| block.genes.gene_count())
+                .sum::<usize>()
+        );
+        assert!(stats.abs_weight_sum >= stats.weight_sum.abs());
+        assert_eq!(stats.upkeep, None);
+        assert!(stats.report().contains(&format!("cells: {}", stats.cells)));
+    }
+
+    #[test]
+    fn symmetry_and_upkeep() {
+        let cell = |x, y, letter| NeuronProperties {
+            index: GridIndex2 { x, y },
+            decoded: crate::dnaparser::DecodedGeneInfo {
+                neuron_type: NeuronType(PropertyValue::from_char(letter).expect("letter")),
+                ..Default::default()
+            },
+        };
+        let mut dna = CreatureDNA {
+            cells: Cells(vec![cell(2, 0, 'R'), cell(4, 0, 'R'), cell(3, 1, 'e')]),
+            ..Default::default()
+        };
+        let costs = CostTable::from_json(r#"{"eye": 0.5, "neuron": 0.25}"#).expect("valid");
+        let stats = CreatureStats::new(&dna, &costs);
+        assert_eq!(stats.symmetry, 1.0);
+        assert_eq!(stats.upkeep, Some(1.25));
+        assert_eq!(
+            stats.bounding_box,
+            Some((GridIndex2 { x: 2, y: 0 }, GridIndex2 { x: 4, y: 1 }))
+        );
+
+        dna.cells[1] = cell(4, 0, 'T');
+        assert!(CreatureStats::new(&dna, &costs).symmetry < 0.5);
+
+        let far = CreatureDNA {
+            cells: Cells(vec![cell(u16::MAX - 1, 0, 'R'), cell(u16::MAX, 0, 'R')]),
+            ..Default::default()
+        };
+        assert_eq!(CreatureStats::new(&far, &costs).symmetry, 1.0);
+        assert!(CostTable::from_json(r#"{"dragon": 1}"#).is_err());
+    }
+}
@@ -0,0 +1,116 @@
+use egui::{Grid, RichText, ScrollArea, Ui};
+
+use crate::dnaparser::CreatureDNA;
+use crate::stats::{CostTable, CreatureStats};
+
+/// Side panel with the statistics of the open creature.
+pub fn stats_ui(ui: &mut Ui, dna: Option<&CreatureDNA>, costs: &CostTable) {
+    let Some(dna) = dna else {
+        ui.label("Open a creature to see its statistics.");
+        return;
+    };
+    let stats = CreatureStats::new(dna, costs);
+
+    ScrollArea::vertical().show(ui, |ui| {
+        Grid::new("stats_summary").num_columns(2).show(ui, |ui| {
+            ui.label("Cells");
+            ui.label(stats.cells.to_string());
+            ui.end_row();
+            for (category, count) in &stats.categories {
+                ui.label(format!("  {}", category.name()));
+                ui.label(count.to_string());
+                ui.end_row();
+            }
+            ui.label("Genes");
+            ui.label(stats.genes.to_string());
+            ui.end_row();
+            ui.label("Connections");
+            ui.label(format!(
+                "{} ({} dangling)",
+                stats.connections, stats.dangling
+            ))
+            .on_hover_text("Output tags of the cells. Dangling ones address no cell.");
+            ui.end_row();
+            ui.label("Weight sum");
+            ui.label(format!("{:.3}", stats.weight_sum));
+            ui.end_row();
+            ui.label("Absolute weight sum");
+            ui.label(format!("{:.3}", stats.abs_weight_sum));
+            ui.end_row();
+            ui.label("Average weight");
+            ui.label(
+                stats
+                    .average_weight()
+                    .map_or("-".to_string(), |average| format!("{average:.3}")),
+            );
+            ui.end_row();
+            ui.label("Bounding box");
+            ui.label(match stats.bounding_box {
+                Some((min, max)) => format!("[{}][{}] to [{}][{}]", min.x, min.y, max.x, max.y),
+                None => "-".to_string(),
+            });
+            ui.end_row();
+            ui.label("Symmetry");
+            ui.label(format!("{:.0}%", stats.symmetry * 100.0))
+                .on_hover_text("Cells mirrored left-right by a cell of the same kind");
+            ui.end_row();
+            ui.label("Energy upkeep");
+            match stats.upkeep {
+                Some(upkeep) => ui.label(format!("{upkeep:.3}")),
+                None => ui
+                    .label(RichText::new("no costs").weak())
+                    .on_hover_text("Set costs in File > Preferences > Energy upkeep"),
+            };
+            ui.end_row();
+        });
+
+        egui::CollapsingHeader::new("Neuron kinds")
+            .default_open(true)
+            .show(ui, |ui| {
+                Grid::new("stats_kinds")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (kind, count) in &stats.kinds {
+                            ui.label(kind.to_name());
+                            ui.label(count.to_string());
+                            ui.end_row();
+                        }
+                    });
+            });
+        egui::CollapsingHeader::new("Genes per layer").show(ui, |ui| {
+            Grid::new("stats_layers")
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    for layer in &stats.layers {
+                        ui.label(format!("DNA {} layer {}", layer.dna_idx, layer.z_level));
+                        ui.label(layer.genes.to_string());
+                        ui.end_row();
+                    }
+                });
+        });
+        egui::CollapsingHeader::new("Fan-in / fan-out").show(ui, |ui| {
+            Grid::new("stats_fans")
+                .num_columns(3)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label(RichText::new("Cell").strong());
+                    ui.label(RichText::new("In").strong());
+                    ui.label(RichText::new("Out").strong());
+                    ui.end_row();
+                    for fan in &stats.fans {
+                        ui.label(format!(
+                            "[{}][{}] {}",
+                            fan.index.x,
+                            fan.index.y,
+                            fan.neuron_type.to_name()
+                        ));
+                        ui.label(fan.fan_in.to_string());
+                        ui.label(fan.fan_out.to_string());
+                        ui.end_row();
+                    }
+                });
+        });
+    });
+}
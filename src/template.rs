@@ -0,0 +1,357 @@
+//! Gene templates: reusable groups of cells or genes like an eye → neuron → fin chain, stored
+//! as one JSON file each in a templates folder of the data dir.
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::dnaparser::{
+    CreatureDNA, DecodedGeneInfo, GeneRecord, GridIndex2, NeuronProperties, PropertyValue,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeneTemplate {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub records: Vec<TemplateRecord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateRecord {
+    /// Position relative to the top left corner of the template.
+    pub offset: GridIndex2,
+    /// Gene encoding like `*e$N#U@z%r^g+H|A{A}A~8&6[J8`.
+    pub gene: String,
+}
+
+/// Records with their grid positions.
+type Records = Vec<(GridIndex2, DecodedGeneInfo)>;
+
+/// Grid a template is stamped into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StampTarget {
+    Cells,
+    Layer { dna_idx: usize, layer_idx: usize },
+}
+
+impl GeneTemplate {
+    /// Template of `records` at their grid positions.
+    pub fn capture(
+        name: &str,
+        description: &str,
+        records: &[(GridIndex2, DecodedGeneInfo)],
+    ) -> Result<Self, String> {
+        let (Some(min_x), Some(min_y)) = (
+            records.iter().map(|(index, _)| index.x).min(),
+            records.iter().map(|(index, _)| index.y).min(),
+        ) else {
+            return Err("no records to save".to_string());
+        };
+        Ok(Self {
+            name: name.trim().to_string(),
+            description: description.trim().to_string(),
+            records: records
+                .iter()
+                .map(|(index, info)| {
+                    Ok(TemplateRecord {
+                        offset: GridIndex2 {
+                            x: index.x - min_x,
+                            y: index.y - min_y,
+                        },
+                        gene: info.encode()?,
+                    })
+                })
+                .collect::<Result<_, String>>()?,
+        })
+    }
+
+    /// Width and height in grid cells.
+    pub fn size(&self) -> (u16, u16) {
+        let width = self.records.iter().map(|r| r.offset.x + 1).max();
+        let height = self.records.iter().map(|r| r.offset.y + 1).max();
+        (width.unwrap_or(0), height.unwrap_or(0))
+    }
+
+    /// The decoded records, with carried tags that are in `taken` moved to free tags. Output
+    /// tags addressing a moved tag follow it, those addressing tags outside the template are
+    /// kept. Also returns the moves.
+    fn remapped(&self, taken: &BTreeSet<u8>) -> Result<(Records, BTreeMap<u8, u8>), String> {
+        let mut records = self
+            .records
+            .iter()
+            .map(|record| DecodedGeneInfo::decode(&record.gene).map(|info| (record.offset, info)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let carried: BTreeSet<u8> = records.iter().map(|(_, info)| info.tag.0.raw()).collect();
+        let mut free = (0..64).filter(|tag| !taken.contains(tag) && !carried.contains(tag));
+        let mut moves = BTreeMap::new();
+        for tag in carried.intersection(taken) {
+            let to = free
+                .next()
+                .ok_or_else(|| "not enough free tags for the template".to_string())?;
+            moves.insert(*tag, to);
+        }
+
+        let remap = |value: &mut PropertyValue| {
+            if let Some(to) = moves
+                .get(&value.raw())
+                .copied()
+                .and_then(PropertyValue::new)
+            {
+                *value = to;
+            }
+        };
+        for (_, info) in records.iter_mut() {
+            remap(&mut info.tag.0);
+            for output in info.output_tags.iter_mut() {
+                remap(&mut output.tag);
+            }
+        }
+        Ok((records, moves))
+    }
+}
+
+/// Stamps `template` into the `target` grid with its top left corner at `origin`. Tags the
+/// grid already uses, carried or addressed by output tags, are remapped; returns those as
+/// (template tag, new tag) pairs.
+pub fn stamp(
+    dna: &mut CreatureDNA,
+    template: &GeneTemplate,
+    target: StampTarget,
+    origin: GridIndex2,
+) -> Result<Vec<(PropertyValue, PropertyValue)>, String> {
+    let existing: Vec<(GridIndex2, &DecodedGeneInfo)> = match target {
+        StampTarget::Cells => dna
+            .cells
+            .iter()
+            .map(|cell| (cell.index, &cell.decoded))
+            .collect(),
+        StampTarget::Layer { dna_idx, layer_idx } => dna
+            .dna
+            .get(dna_idx)
+            .and_then(|block| block.genes.get(layer_idx))
+            .ok_or_else(|| "DNA layer not found".to_string())?
+            .genes
+            .iter()
+            .map(|gene| (gene.index, &gene.decoded))
+            .collect(),
+    };
+    // A stamped record keeping a tag that existing output tags address would get their inputs.
+    let taken_tags: BTreeSet<u8> = existing
+        .iter()
+        .flat_map(|(_, info)| {
+            std::iter::once(info.tag.0.raw())
+                .chain(info.output_tags.iter().map(|output| output.tag.raw()))
+        })
+        .collect();
+    let taken_indices: BTreeSet<(u16, u16)> = existing
+        .iter()
+        .map(|(index, _)| (index.x, index.y))
+        .collect();
+
+    let (records, moves) = template.remapped(&taken_tags)?;
+    let mut placed = Vec::with_capacity(records.len());
+    for (offset, info) in records {
+        let (Some(x), Some(y)) = (
+            origin.x.checked_add(offset.x),
+            origin.y.checked_add(offset.y),
+        ) else {
+            return Err("the template does not fit into the grid".to_string());
+        };
+        if taken_indices.contains(&(x, y)) {
+            return Err(format!("[{x}][{y}] is already taken"));
+        }
+        placed.push((GridIndex2 { x, y }, info));
+    }
+
+    match target {
+        StampTarget::Cells => {
+            dna.cells.extend(
+                placed
+                    .into_iter()
+                    .map(|(index, decoded)| NeuronProperties { index, decoded }),
+            );
+        }
+        StampTarget::Layer { dna_idx, layer_idx } => {
+            if let Some(layer) = dna
+                .dna
+                .get_mut(dna_idx)
+                .and_then(|block| block.genes.get_mut(layer_idx))
+            {
+                layer.genes.extend(
+                    placed
+                        .into_iter()
+                        .map(|(index, decoded)| GeneRecord { index, decoded }),
+                );
+            }
+        }
+    }
+    Ok(moves
+        .into_iter()
+        .filter_map(|(from, to)| Some((PropertyValue::new(from)?, PropertyValue::new(to)?)))
+        .collect())
+}
+
+/// A template file of the templates folder.
+pub struct StoredTemplate {
+    pub path: PathBuf,
+    pub template: Result<GeneTemplate, String>,
+}
+
+/// Reads the `.json` templates of `dir`, sorted by name. A missing folder has none.
+pub fn load_templates(dir: &Path) -> Result<Vec<StoredTemplate>, String> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(format!("failed to read '{}': {err}", dir.display())),
+    };
+    let mut templates: Vec<StoredTemplate> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .map(|path| {
+            let template = std::fs::read(&path)
+                .map_err(|err| format!("failed to read '{}': {err}", path.display()))
+                .and_then(|bytes| {
+                    serde_json::from_slice(&bytes)
+                        .map_err(|err| format!("failed to parse '{}': {err}", path.display()))
+                });
+            StoredTemplate { path, template }
+        })
+        .collect();
+    templates.sort_by_key(|stored| match &stored.template {
+        Ok(template) => template.name.to_lowercase(),
+        Err(_) => stored.path.to_string_lossy().to_lowercase(),
+    });
+    Ok(templates)
+}
+
+/// Writes `template` to `dir`, named after the template and replacing one of the same name.
+/// Refuses to replace a file holding another template whose name maps to the same file name.
+pub fn save_template(dir: &Path, template: &GeneTemplate) -> Result<PathBuf, String> {
+    if template.name.is_empty() {
+        return Err("the template needs a name".to_string());
+    }
+    let file_name: String = template
+        .name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let path = dir.join(format!("{file_name}.json"));
+    if path.exists() {
+        let existing = std::fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<GeneTemplate>(&bytes).ok());
+        match existing {
+            Some(existing) if existing.name == template.name => {}
+            Some(existing) => {
+                return Err(format!(
+                    "'{}' already holds the template '{}'",
+                    path.display(),
+                    existing.name
+                ));
+            }
+            None => return Err(format!("'{}' already exists", path.display())),
+        }
+    }
+    let json = serde_json::to_string_pretty(template)
+        .map_err(|err| format!("failed to serialize template: {err}"))?;
+    std::fs::create_dir_all(dir)
+        .and_then(|()| std::fs::write(&path, json))
+        .map_err(|err| format!("failed to write '{}': {err}", path.display()))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dnaparser::OutputTag;
+
+    #[test]
+    fn stamps_with_tags_remapped() {
+        let mut dna = crate::read_creature("data/e5.txt").expect("failed to read data/e5.txt");
+        // The output tags of all cells address nearly every tag, leaving none to move to.
+        dna.cells.truncate(2);
+        let records: Vec<(GridIndex2, DecodedGeneInfo)> = dna.cells[..2]
+            .iter()
+            .map(|cell| (cell.index, cell.decoded.clone()))
+            .collect();
+        let template = GeneTemplate::capture(" pair ", "", &records).expect("has records");
+        assert_eq!(template.name, "pair");
+        assert!(template.records.iter().any(|r| r.offset.x == 0));
+        assert!(template.records.iter().any(|r| r.offset.y == 0));
+
+        let cells = dna.cells.len();
+        let occupied = dna.cells[0].index;
+        let taken = stamp(&mut dna, &template, StampTarget::Cells, occupied);
+        assert!(taken.is_err());
+        assert_eq!(dna.cells.len(), cells);
+
+        let origin = GridIndex2 { x: 100, y: 100 };
+        let moves = stamp(&mut dna, &template, StampTarget::Cells, origin).expect("stamped");
+        assert_eq!(dna.cells.len(), cells + 2);
+        // The stamped cells carried tags of the original cells, so all of them moved.
+        assert!(!moves.is_empty());
+        for (stamped, (_, original)) in dna.cells[cells..].iter().zip(&records) {
+            assert_ne!(stamped.decoded.tag, original.tag);
+            assert_eq!(stamped.decoded.neuron_type, original.neuron_type);
+            for (output, original) in stamped
+                .decoded
+                .output_tags
+                .iter()
+                .zip(&original.output_tags)
+            {
+                let moved = moves.iter().find(|(from, _)| *from == original.tag);
+                assert_eq!(output.tag, moved.map_or(original.tag, |(_, to)| *to));
+            }
+        }
+
+        // A layer that only addresses the tag of the first record still takes it.
+        let layer_idx = dna.dna[0].genes.add_layer();
+        let mut addressing = DecodedGeneInfo::default();
+        addressing.output_tags.push(OutputTag {
+            tag: records[0].1.tag.0,
+            weight: PropertyValue::default(),
+        });
+        addressing.tag.0 = PropertyValue::new((records[0].1.tag.0.raw() + 1) % 64).unwrap();
+        dna.dna[0].genes[layer_idx].genes.push(GeneRecord {
+            index: GridIndex2 { x: 0, y: 0 },
+            decoded: addressing,
+        });
+        let target = StampTarget::Layer {
+            dna_idx: 0,
+            layer_idx,
+        };
+        let moves = stamp(&mut dna, &template, target, origin).expect("stamped");
+        assert!(moves.iter().any(|(from, _)| *from == records[0].1.tag.0));
+    }
+
+    #[test]
+    fn templates_round_trip_through_files() {
+        let dir = std::env::temp_dir().join(format!("gridworld-templates-{}", std::process::id()));
+        let dna = crate::read_creature("data/e5.txt").expect("failed to read data/e5.txt");
+        let records = [(GridIndex2 { x: 3, y: 4 }, dna.cells[0].decoded.clone())];
+        let template = GeneTemplate::capture("eye/fin", "a chain", &records).expect("has records");
+        assert_eq!(template.size(), (1, 1));
+
+        let path = save_template(&dir, &template).expect("saved");
+        assert_eq!(path.file_name().unwrap(), "eye_fin.json");
+        assert_eq!(save_template(&dir, &template), Ok(path.clone()));
+        let clash = GeneTemplate {
+            name: "eye fin".to_string(),
+            ..template.clone()
+        };
+        assert!(save_template(&dir, &clash).is_err());
+        let loaded = load_templates(&dir).expect("loaded");
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].template.as_ref(), Ok(&template));
+
+        std::fs::remove_dir_all(&dir).expect("removed");
+        assert!(load_templates(&dir).expect("missing folder").is_empty());
+    }
+}
@@ -0,0 +1,219 @@
+use std::path::PathBuf;
+
+use egui::{Color32, RichText, ScrollArea, Ui};
+
+use crate::document::Document;
+use crate::template::{self, GeneTemplate, StoredTemplate};
+
+/// Side panel listing the gene templates, saving the marked records as a new one and stamping
+/// the selected one into the grid at the cursor.
+pub struct TemplateWidget {
+    folder: PathBuf,
+    templates: Vec<StoredTemplate>,
+    loaded: bool,
+    selected: Option<PathBuf>,
+    name: String,
+    description: String,
+    message: Option<Result<String, String>>,
+    /// Template file waiting for the user to confirm its deletion, with the template name.
+    pending_delete: Option<(PathBuf, String)>,
+}
+
+impl TemplateWidget {
+    pub fn new(folder: PathBuf) -> Self {
+        Self {
+            folder,
+            templates: Vec::new(),
+            loaded: false,
+            selected: None,
+            name: String::new(),
+            description: String::new(),
+            message: None,
+            pending_delete: None,
+        }
+    }
+
+    fn reload(&mut self) {
+        match template::load_templates(&self.folder) {
+            Ok(templates) => self.templates = templates,
+            Err(err) => self.message = Some(Err(err)),
+        }
+        self.loaded = true;
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui, doc: Option<&mut Document>) {
+        if !self.loaded {
+            self.reload();
+        }
+        let Some(doc) = doc else {
+            ui.label("Open a creature to use templates.");
+            return;
+        };
+
+        let marked = doc.dna_widget.marked_records(&doc.dna);
+        ui.label(RichText::new("Save as template").strong());
+        ui.label(format!(
+            "{} record(s) marked. Ctrl+click cells or genes in the grid to mark them.",
+            marked.len()
+        ));
+        ui.horizontal(|ui| {
+            ui.label("Name");
+            ui.text_edit_singleline(&mut self.name);
+        });
+        ui.add(
+            egui::TextEdit::multiline(&mut self.description)
+                .hint_text("Description")
+                .desired_rows(2),
+        );
+        let can_save = !marked.is_empty() && !self.name.trim().is_empty();
+        if ui
+            .add_enabled(can_save, egui::Button::new("Save template"))
+            .clicked()
+        {
+            match GeneTemplate::capture(&self.name, &self.description, &marked) {
+                Ok(template) => {
+                    let saved = template::save_template(&self.folder, &template);
+                    if saved.is_ok() {
+                        self.name.clear();
+                        self.description.clear();
+                    }
+                    self.message = Some(saved.map(|_| format!("Saved '{}'.", template.name)));
+                    self.reload();
+                }
+                Err(err) => self.message = Some(Err(err)),
+            }
+        }
+        ui.separator();
+
+        let mut reload = false;
+        let target = doc.dna_widget.stamp_target();
+        let selected = self
+            .templates
+            .iter()
+            .find(|stored| Some(&stored.path) == self.selected.as_ref() && stored.template.is_ok());
+        ui.horizontal(|ui| {
+            let stamp_text = match target {
+                Some((_, origin)) => format!("Stamp at [{}][{}]", origin.x, origin.y),
+                None => "Stamp".to_string(),
+            };
+            let can_stamp = target.is_some() && selected.is_some();
+            if ui
+                .add_enabled(can_stamp, egui::Button::new(stamp_text))
+                .on_hover_text(
+                    "Adds the template with its top left corner at the grid cursor. Tags the \
+                     grid already uses are replaced by free ones.",
+                )
+                .on_disabled_hover_text("Select a template and open the cell grid or a layer")
+                .clicked()
+                && let (Some((target, origin)), Some(Ok(template))) =
+                    (target, selected.map(|stored| &stored.template))
+            {
+                self.message = Some(template::stamp(&mut doc.dna, template, target, origin).map(
+                    |moves| {
+                        let moves: Vec<String> = moves
+                            .iter()
+                            .map(|(from, to)| {
+                                format!(
+                                    "{}→{}",
+                                    from.to_char().unwrap_or_default(),
+                                    to.to_char().unwrap_or_default()
+                                )
+                            })
+                            .collect();
+                        if moves.is_empty() {
+                            format!("Stamped '{}'.", template.name)
+                        } else {
+                            format!("Stamped '{}', tags {}.", template.name, moves.join(", "))
+                        }
+                    },
+                ));
+            }
+            reload = ui.button("Reload").clicked();
+        });
+        if reload {
+            self.reload();
+        }
+        match &self.message {
+            Some(Ok(message)) => {
+                ui.label(message);
+            }
+            Some(Err(err)) => {
+                ui.colored_label(Color32::RED, err);
+            }
+            None => {}
+        }
+        ui.separator();
+
+        ScrollArea::vertical().show(ui, |ui| {
+            if self.templates.is_empty() {
+                ui.label(format!("No templates in {}.", self.folder.display()));
+            }
+            for stored in &self.templates {
+                let is_selected = self.selected.as_ref() == Some(&stored.path);
+                match &stored.template {
+                    Ok(template) => {
+                        let (width, height) = template.size();
+                        let response = ui.selectable_label(
+                            is_selected,
+                            format!(
+                                "{} ({} records, {width}×{height})",
+                                template.name,
+                                template.records.len()
+                            ),
+                        );
+                        let response = if template.description.is_empty() {
+                            response
+                        } else {
+                            response.on_hover_text(&template.description)
+                        };
+                        if response.clicked() {
+                            self.selected = Some(stored.path.clone());
+                        }
+                        response.context_menu(|ui| {
+                            if ui.button("Delete...").clicked() {
+                                self.pending_delete =
+                                    Some((stored.path.clone(), template.name.clone()));
+                                ui.close();
+                            }
+                        });
+                        if is_selected && !template.description.is_empty() {
+                            ui.label(RichText::new(&template.description).weak());
+                        }
+                    }
+                    Err(err) => {
+                        ui.colored_label(Color32::RED, err);
+                    }
+                }
+            }
+        });
+
+        self.delete_prompt_ui(ui.ctx());
+    }
+
+    fn delete_prompt_ui(&mut self, ctx: &egui::Context) {
+        let Some((path, name)) = self.pending_delete.clone() else {
+            return;
+        };
+        let mut delete = false;
+        let mut cancel = false;
+        egui::Window::new("Delete Template")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("Delete the template '{name}'?"));
+                ui.horizontal(|ui| {
+                    delete = ui.button("Delete").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        if delete {
+            if let Err(err) = std::fs::remove_file(&path) {
+                self.message = Some(Err(format!("failed to delete '{}': {err}", path.display())));
+            }
+            self.reload();
+        }
+        if delete || cancel {
+            self.pending_delete = None;
+        }
+    }
+}
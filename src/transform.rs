@@ -0,0 +1,168 @@
+//! Whole-grid edits: flipping the cells or the genes of a layer, and random point mutations.
+use std::hash::{BuildHasher, Hasher};
+
+use crate::dnaparser::{CreatureDNA, DecodedGeneInfo, DnaLayer, GridIndex2};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    /// Mirror left and right.
+    Horizontal,
+    /// Mirror top and bottom.
+    Vertical,
+}
+
+/// Mirrors the cells within their bounding box. The records of the DNA blocks move with the
+/// dna cells they sit on. Only positions change, direction-dependent properties are kept.
+/// Nothing changes if a block record sits where its mirror would be off the grid.
+pub fn flip_cells(dna: &mut CreatureDNA, axis: Axis) {
+    let Some(flip) = Flip::of(dna.cells.iter().map(|cell| cell.index), axis) else {
+        return;
+    };
+    let mut indices: Vec<&mut GridIndex2> =
+        dna.cells.iter_mut().map(|cell| &mut cell.index).collect();
+    for block in dna.dna.iter_mut() {
+        indices.extend(block.dna_location.as_mut());
+        indices.extend(block.dna_name.as_mut().map(|record| &mut record.index));
+        indices.extend(block.dna_creator.as_mut().map(|record| &mut record.index));
+    }
+    flip.apply_all(indices);
+}
+
+/// Mirrors the genes of one layer within their bounding box.
+pub fn flip_layer(layer: &mut DnaLayer, axis: Axis) {
+    let Some(flip) = Flip::of(layer.genes.iter().map(|gene| gene.index), axis) else {
+        return;
+    };
+    flip.apply_all(layer.genes.iter_mut().map(|gene| &mut gene.index).collect());
+}
+
+struct Flip {
+    axis: Axis,
+    /// Sum of the smallest and largest coordinate along the axis, which may not fit a `u16`.
+    span: u32,
+}
+
+impl Flip {
+    fn of(indices: impl Iterator<Item = GridIndex2>, axis: Axis) -> Option<Self> {
+        let coordinates: Vec<u16> = indices
+            .map(|index| match axis {
+                Axis::Horizontal => index.x,
+                Axis::Vertical => index.y,
+            })
+            .collect();
+        let min = coordinates.iter().min()?;
+        let max = coordinates.iter().max()?;
+        Some(Self {
+            axis,
+            span: u32::from(*min) + u32::from(*max),
+        })
+    }
+
+    /// The mirrored index, `None` if it is off the grid.
+    fn apply(&self, index: GridIndex2) -> Option<GridIndex2> {
+        let mirror = |coordinate: u16| {
+            self.span
+                .checked_sub(u32::from(coordinate))
+                .and_then(|mirrored| u16::try_from(mirrored).ok())
+        };
+        Some(match self.axis {
+            Axis::Horizontal => GridIndex2 {
+                x: mirror(index.x)?,
+                y: index.y,
+            },
+            Axis::Vertical => GridIndex2 {
+                x: index.x,
+                y: mirror(index.y)?,
+            },
+        })
+    }
+
+    /// Mirrors all `indices`, or none of them if one would leave the grid.
+    fn apply_all(&self, indices: Vec<&mut GridIndex2>) {
+        let Some(mirrored) = indices
+            .iter()
+            .map(|index| self.apply(**index))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return;
+        };
+        for (index, mirrored) in indices.into_iter().zip(mirrored) {
+            *index = mirrored;
+        }
+    }
+}
+
+/// Nudges one property, the bias or one output weight by 1 to 3 steps, picked by `bits`.
+/// Returns a description of the change, `None` if the value was already at its limit.
+pub fn mutate(info: &mut DecodedGeneInfo, bits: u64) -> Option<String> {
+    let fields = 8 + 1 + info.output_tags.len();
+    let field = (bits % fields as u64) as usize;
+    let steps = (bits / fields as u64 % 3 + 1) as u8;
+    let up = (bits / fields as u64 / 3).is_multiple_of(2);
+
+    let (name, value) = match field {
+        0..8 => (format!("property {field}"), &mut info.properties[field].0),
+        8 => ("bias".to_owned(), &mut info.bias.0),
+        _ => {
+            let output = &mut info.output_tags[field - 9];
+            (
+                format!("output weight to {}", output.tag.to_char()?),
+                &mut output.weight,
+            )
+        }
+    };
+    let before = *value;
+    *value = if up {
+        before.saturating_add(steps)
+    } else {
+        before.saturating_sub(steps)
+    };
+    (*value != before).then(|| format!("{name}: {} -> {}", before.raw(), value.raw()))
+}
+
+/// Random bits for `mutate`, different on every call.
+pub fn random_bits() -> u64 {
+    let mut hasher = std::hash::RandomState::new().build_hasher();
+    hasher.write_u64(0);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flipping_twice_restores_the_creature() {
+        let original = crate::read_creature("data/e5.txt").expect("failed to read data/e5.txt");
+        let mut dna = original.clone();
+        flip_cells(&mut dna, Axis::Horizontal);
+        assert_ne!(dna, original);
+        let location = dna.dna[0].dna_location.expect("block has a location");
+        assert!(crate::validate::is_dna_cell(&dna, location));
+        flip_cells(&mut dna, Axis::Horizontal);
+        assert_eq!(dna, original);
+
+        flip_layer(&mut dna.dna[0].genes[0], Axis::Vertical);
+        flip_layer(&mut dna.dna[0].genes[0], Axis::Vertical);
+        assert_eq!(dna, original);
+
+        let layer = &mut dna.dna[0].genes[0];
+        layer.genes.truncate(2);
+        layer.genes[0].index.x = u16::MAX;
+        layer.genes[1].index.x = u16::MAX - 1;
+        flip_layer(layer, Axis::Horizontal);
+        assert_eq!(layer.genes[0].index.x, u16::MAX - 1);
+        assert_eq!(layer.genes[1].index.x, u16::MAX);
+    }
+
+    #[test]
+    fn mutation_changes_one_field_within_range() {
+        let mut info = DecodedGeneInfo::default();
+        assert_eq!(mutate(&mut info, 0), Some("property 0: 0 -> 1".to_owned()));
+        assert_eq!(info.properties[0].0.raw(), 1);
+
+        let bits = 8 + 9 * 2 + 9 * 3;
+        assert_eq!(mutate(&mut info, bits), None);
+        assert_eq!(info.bias.0.raw(), 0);
+    }
+}
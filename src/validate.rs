@@ -0,0 +1,255 @@
+//! Consistency checks for things the parser accepts but the game would not use as intended.
+use std::fmt;
+
+use crate::dnaparser::{CreatureDNA, DecodedGeneInfo, DnaData, GridIndex2};
+use crate::merge::{DnaField, RecordLocation};
+
+/// Neuron kinds whose property 0 is the z-level of a DNA layer: DNA copier and executor.
+const LAYER_REFERENCING_NEURONS: [char; 2] = ['K', 'L'];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    /// Several layers of one DNA block share a z-level. They are merged when the file is read.
+    DuplicateZLevel { dna_idx: usize, z_level: u16 },
+    /// A copier or executor refers to a z-level no layer has. Genes are checked against their
+    /// own DNA block, cells against all blocks.
+    MissingLayer {
+        location: RecordLocation,
+        z_level: u16,
+    },
+    /// A DNA block has no `dna_location`.
+    MissingDnaLocation { dna_idx: usize },
+    /// A record of a DNA block points at a position without a "dna" cell.
+    NotADnaCell {
+        dna_idx: usize,
+        field: DnaField,
+        index: GridIndex2,
+    },
+}
+
+impl Issue {
+    /// The DNA block the issue belongs to, `None` for issues with cells.
+    pub fn dna_idx(&self) -> Option<usize> {
+        match self {
+            Issue::DuplicateZLevel { dna_idx, .. }
+            | Issue::MissingDnaLocation { dna_idx }
+            | Issue::NotADnaCell { dna_idx, .. } => Some(*dna_idx),
+            Issue::MissingLayer {
+                location: RecordLocation::Gene { dna_idx, .. },
+                ..
+            } => Some(*dna_idx),
+            Issue::MissingLayer {
+                location: RecordLocation::Cell(_),
+                ..
+            } => None,
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::DuplicateZLevel { dna_idx, z_level } => {
+                write!(f, "dna {dna_idx} has several layers with z-level {z_level}")
+            }
+            Issue::MissingLayer { location, z_level } => {
+                write!(f, "{location} refers to missing layer {z_level}")
+            }
+            Issue::MissingDnaLocation { dna_idx } => write!(f, "dna {dna_idx} has no location"),
+            Issue::NotADnaCell {
+                dna_idx,
+                field,
+                index,
+            } => write!(
+                f,
+                "dna {dna_idx} {} at [{}][{}] is not on a dna cell",
+                field.name(),
+                index.x,
+                index.y
+            ),
+        }
+    }
+}
+
+pub fn validate(dna: &CreatureDNA) -> Vec<Issue> {
+    let mut issues = Vec::new();
+
+    for cell in dna.cells.iter() {
+        if let Some(z_level) = referenced_z_level(&cell.decoded)
+            && !dna.dna.iter().any(|block| has_layer(block, z_level))
+        {
+            issues.push(Issue::MissingLayer {
+                location: RecordLocation::Cell(cell.index),
+                z_level,
+            });
+        }
+    }
+
+    for (dna_idx, block) in dna.dna.iter().enumerate() {
+        if block.dna_location.is_none() {
+            issues.push(Issue::MissingDnaLocation { dna_idx });
+        }
+        let records = [
+            (DnaField::Location, block.dna_location),
+            (
+                DnaField::Name,
+                block.dna_name.as_ref().map(|record| record.index),
+            ),
+            (
+                DnaField::Creator,
+                block.dna_creator.as_ref().map(|record| record.index),
+            ),
+        ];
+        for (field, index) in records {
+            if let Some(index) = index
+                && !is_dna_cell(dna, index)
+            {
+                issues.push(Issue::NotADnaCell {
+                    dna_idx,
+                    field,
+                    index,
+                });
+            }
+        }
+
+        let mut seen = Vec::new();
+        for layer in block.genes.iter() {
+            if seen.contains(&layer.z_level) {
+                let issue = Issue::DuplicateZLevel {
+                    dna_idx,
+                    z_level: layer.z_level,
+                };
+                if !issues.contains(&issue) {
+                    issues.push(issue);
+                }
+            }
+            seen.push(layer.z_level);
+        }
+
+        for layer in block.genes.iter() {
+            for gene in &layer.genes {
+                if let Some(z_level) = referenced_z_level(&gene.decoded)
+                    && !has_layer(block, z_level)
+                {
+                    issues.push(Issue::MissingLayer {
+                        location: RecordLocation::Gene {
+                            dna_idx,
+                            z_level: layer.z_level,
+                            index: gene.index,
+                        },
+                        z_level,
+                    });
+                }
+            }
+        }
+    }
+
+    issues
+}
+
+pub fn is_dna_cell(dna: &CreatureDNA, index: GridIndex2) -> bool {
+    dna.cells
+        .get_cell_at(index.x, index.y)
+        .is_some_and(|info| info.neuron_type.is_dna())
+}
+
+fn referenced_z_level(info: &DecodedGeneInfo) -> Option<u16> {
+    let neuron_char = info.neuron_type.0.to_char()?;
+    LAYER_REFERENCING_NEURONS
+        .contains(&neuron_char)
+        .then(|| u16::from(info.properties[0].0.as_int()))
+}
+
+fn has_layer(block: &DnaData, z_level: u16) -> bool {
+    block.genes.iter().any(|layer| layer.z_level == z_level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dnaparser::{DnaLayer, NeuronType, PropertyValue};
+
+    #[test]
+    fn checks_dna_block_placement() {
+        let mut dna = crate::read_creature("data/dna2.txt").expect("failed to read data/dna2.txt");
+        assert!(
+            validate(&dna)
+                .iter()
+                .all(|issue| !matches!(issue, Issue::NotADnaCell { .. }))
+        );
+
+        let location = dna.dna[0].dna_location.expect("block has a location");
+        let elsewhere = dna
+            .cells
+            .iter()
+            .map(|cell| cell.index)
+            .find(|index| !is_dna_cell(&dna, *index))
+            .expect("creature has other cells");
+        dna.dna[0]
+            .dna_creator
+            .as_mut()
+            .expect("creator record")
+            .index = elsewhere;
+        dna.dna[1].place_at(None);
+        assert_eq!(
+            validate(&dna)
+                .into_iter()
+                .filter(|issue| !matches!(issue, Issue::MissingLayer { .. }))
+                .collect::<Vec<_>>(),
+            vec![
+                Issue::NotADnaCell {
+                    dna_idx: 0,
+                    field: DnaField::Creator,
+                    index: elsewhere,
+                },
+                Issue::MissingDnaLocation { dna_idx: 1 },
+            ]
+        );
+
+        dna.dna[0].place_at(Some(location));
+        assert!(!validate(&dna).contains(&Issue::NotADnaCell {
+            dna_idx: 0,
+            field: DnaField::Creator,
+            index: elsewhere,
+        }));
+    }
+
+    #[test]
+    fn finds_duplicate_and_missing_layers() {
+        let mut dna = crate::read_creature("data/e5.txt").expect("failed to read data/e5.txt");
+        for block in dna.dna.iter_mut() {
+            block.genes.retain(|layer| layer.z_level == 0);
+        }
+        for cell in dna.cells.iter_mut() {
+            if referenced_z_level(&cell.decoded).is_some() {
+                cell.decoded.properties[0].0 = PropertyValue::new(0).expect("valid value");
+            }
+        }
+        dna.dna.truncate(1);
+        assert!(has_layer(&dna.dna[0], 0));
+        assert_eq!(validate(&dna), vec![]);
+
+        dna.dna[0].genes.push(DnaLayer {
+            z_level: 0,
+            genes: Vec::new(),
+        });
+        let copier = &mut dna.cells[0].decoded;
+        copier.neuron_type = NeuronType(PropertyValue::from_char('K').expect("valid letter"));
+        copier.properties[0].0 = PropertyValue::new(7).expect("valid value");
+        let index = dna.cells[0].index;
+
+        assert_eq!(
+            validate(&dna),
+            vec![
+                Issue::MissingLayer {
+                    location: RecordLocation::Cell(index),
+                    z_level: 7,
+                },
+                Issue::DuplicateZLevel {
+                    dna_idx: 0,
+                    z_level: 0,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! Recently used files, pinned favorites and named workspaces of open creatures.
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// Entries kept in the recent files list.
+pub const MAX_RECENT: usize = 10;
+
+/// A saved set of open creatures, to switch between projects.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Workspace {
+    pub name: String,
+    /// Open files in tab order.
+    pub files: Vec<WorkspaceFile>,
+    /// Tab that was active.
+    #[serde(default)]
+    pub active: usize,
+    #[serde(default)]
+    pub layout: PanelLayout,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceFile {
+    pub path: PathBuf,
+    /// Item selected in the sidebar.
+    #[serde(default)]
+    pub selected_path: Option<String>,
+}
+
+/// Visibility and widths of the side panels.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct PanelLayout {
+    pub show_library: bool,
+    #[serde(default)]
+    pub show_templates: bool,
+    #[serde(default)]
+    pub show_stats: bool,
+    pub sidebar_width: Option<f32>,
+    pub library_width: Option<f32>,
+}
+
+/// Moves `path` to the front of `recent`, dropping the oldest entries beyond `MAX_RECENT`.
+pub fn push_recent(recent: &mut Vec<PathBuf>, path: &Path) {
+    recent.retain(|other| other != path);
+    recent.insert(0, path.to_path_buf());
+    recent.truncate(MAX_RECENT);
+}
+
+/// Pins `path`, or unpins it if it already is a favorite.
+pub fn toggle_favorite(favorites: &mut Vec<PathBuf>, path: &Path) {
+    match favorites.iter().position(|other| other == path) {
+        Some(idx) => {
+            favorites.remove(idx);
+        }
+        None => favorites.push(path.to_path_buf()),
+    }
+}
+
+/// Stores `workspace`, replacing one of the same name.
+pub fn save_workspace(workspaces: &mut Vec<Workspace>, workspace: Workspace) {
+    match workspaces
+        .iter_mut()
+        .find(|other| other.name == workspace.name)
+    {
+        Some(other) => *other = workspace,
+        None => {
+            workspaces.push(workspace);
+            workspaces.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_files_are_unique_and_limited() {
+        let mut recent = Vec::new();
+        for idx in 0..MAX_RECENT + 3 {
+            push_recent(&mut recent, Path::new(&format!("{idx}.txt")));
+        }
+        push_recent(&mut recent, Path::new("5.txt"));
+        assert_eq!(recent.len(), MAX_RECENT);
+        assert_eq!(recent[0], PathBuf::from("5.txt"));
+        assert_eq!(recent[1], PathBuf::from(format!("{}.txt", MAX_RECENT + 2)));
+        assert_eq!(
+            recent.iter().filter(|path| path.ends_with("5.txt")).count(),
+            1
+        );
+
+        let mut favorites = Vec::new();
+        toggle_favorite(&mut favorites, Path::new("a.txt"));
+        toggle_favorite(&mut favorites, Path::new("b.txt"));
+        toggle_favorite(&mut favorites, Path::new("a.txt"));
+        assert_eq!(favorites, vec![PathBuf::from("b.txt")]);
+    }
+
+    #[test]
+    fn workspaces_are_replaced_by_name() {
+        let workspace = |name: &str, files: &[&str]| Workspace {
+            name: name.to_string(),
+            files: files
+                .iter()
+                .map(|path| WorkspaceFile {
+                    path: PathBuf::from(path),
+                    selected_path: None,
+                })
+                .collect(),
+            active: 0,
+            layout: PanelLayout::default(),
+        };
+        let mut workspaces = Vec::new();
+        save_workspace(&mut workspaces, workspace("predators", &["wolf.txt"]));
+        save_workspace(&mut workspaces, workspace("plants", &["fern.txt"]));
+        save_workspace(
+            &mut workspaces,
+            workspace("predators", &["wolf.txt", "shark.txt"]),
+        );
+        let names: Vec<&str> = workspaces.iter().map(|ws| ws.name.as_str()).collect();
+        assert_eq!(names, ["plants", "predators"]);
+        assert_eq!(workspaces[1].files.len(), 2);
+    }
+}